@@ -0,0 +1,51 @@
+import Model from "src/Model";
+import ShootOptions from "src/ShootOptions";
+import { calcDmgProbs as ktCalcDmgProbs } from 'src/CalcEngineShoot';
+import {
+  DeadzoneModel,
+  DeadzoneOptions,
+  NormalizedMetrics,
+  deadzoneCalcNormalizedMetrics,
+  normalizeDmgProbs,
+} from "src/DiceSim/pkg/dice_sim";
+
+// a profile and the reference target it's shot at, for one game
+export interface KtShootProfile {
+  attacker: Model;
+  target: Model;
+  options: ShootOptions;
+}
+
+export interface DeadzoneShootProfile {
+  attacker: DeadzoneModel;
+  target: DeadzoneModel;
+  options: DeadzoneOptions;
+}
+
+export interface CrossGameComparison {
+  kt: NormalizedMetrics;
+  deadzone: NormalizedMetrics;
+  killProbRatio: number; // deadzone over kt; Infinity or NaN when kt can't kill
+}
+
+// KT's shooting engine is still TS, so its dmg probs are normalized by the wasm module the same
+// way Deadzone's are; each target's own wounds/hp are what kill prob and wounds fraction are vs
+export function compareKtAndDeadzone(
+  kt: KtShootProfile,
+  deadzone: DeadzoneShootProfile,
+): CrossGameComparison {
+  const ktMetrics = normalizeDmgProbs(
+    ktCalcDmgProbs(kt.attacker, kt.target, kt.options),
+    kt.target.wounds,
+  );
+  const deadzoneMetrics = deadzoneCalcNormalizedMetrics(
+    deadzone.attacker,
+    deadzone.target,
+    deadzone.options,
+  );
+  return {
+    kt: ktMetrics,
+    deadzone: deadzoneMetrics,
+    killProbRatio: deadzoneMetrics.killProb / ktMetrics.killProb,
+  };
+}
@@ -1,6 +1,14 @@
 import { randomInt } from "mathjs";
 import { executeAndMeasureMs, } from 'src/Util';
-import { DeadzoneModel, DeadzoneOptions, deadzoneCalcDmgProbs } from "src/DiceSim/pkg/dice_sim";
+import {
+  DeadzoneModel,
+  DeadzoneOptions,
+  DieCountValue,
+  RoundDmgStats,
+  deadzoneCalcDmgByRound,
+  deadzoneCalcDmgProbs,
+  deadzoneCalcMarginalDieValues,
+} from "src/DiceSim/pkg/dice_sim";
 
 
 export function calcDmgProbs(
@@ -17,3 +25,28 @@ export function calcDmgProbs(
 
   return wasmAnswer;
 }
+
+// list results were bare arrays before they got a resultSchemaVersion, and results saved by older
+// app versions still are; this reads either shape
+export function listItems<T>(result: T[] | { items: T[] }): T[] {
+  return Array.isArray(result) ? result : result.items;
+}
+
+export function calcDmgByRound(
+  attacker: DeadzoneModel,
+  defender: DeadzoneModel,
+  options: DeadzoneOptions = new DeadzoneOptions(),
+): RoundDmgStats[]
+{
+  return listItems(deadzoneCalcDmgByRound(attacker, defender, options));
+}
+
+export function calcMarginalDieValues(
+  attacker: DeadzoneModel,
+  defender: DeadzoneModel,
+  maxNumDice: number,
+  options: DeadzoneOptions = new DeadzoneOptions(),
+): DieCountValue[]
+{
+  return listItems(deadzoneCalcMarginalDieValues(attacker, defender, options, maxNumDice));
+}
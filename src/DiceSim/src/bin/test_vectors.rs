@@ -0,0 +1,12 @@
+// writes the shared test vectors as JSON, to the given path or else stdout:
+// cargo run --features test-vectors --bin test_vectors -- vectors.json
+use std::io::Write;
+
+fn main() -> std::io::Result<()> {
+    let vectors = dice_sim::test_vectors::generate_test_vectors();
+    let json = serde_json::to_string_pretty(&vectors)?;
+    match std::env::args().nth(1) {
+        Some(path) => std::fs::write(path, json + "\n"),
+        None => writeln!(std::io::stdout(), "{json}"),
+    }
+}
@@ -0,0 +1,64 @@
+use serde::{Serialize, Serializer};
+use wasm_bindgen::prelude::*;
+
+use crate::common::stat_validation::RangedStats;
+use crate::common::ts_types::StatRanges;
+
+// ordered from worst to best for the blocker
+#[wasm_bindgen]
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum BlockDieFace {
+    AttackerDown, // skull
+    BothDown,
+    Push,
+    DefenderStumbles,
+    DefenderDown, // pow
+}
+
+// as the number wasm-bindgen hands JS, rather than serde's variant name
+impl Serialize for BlockDieFace {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_i32(*self as i32)
+    }
+}
+
+#[wasm_bindgen]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockDiceOptions {
+    #[wasm_bindgen(js_name = numDice)]
+    pub num_dice: i32,
+    #[wasm_bindgen(js_name = attackerChooses)]
+    pub attacker_chooses: bool, // blocker is stronger, so picks the best die; otherwise defender picks the worst
+    #[wasm_bindgen(js_name = hasReroll)]
+    pub has_reroll: bool, // whole block can be rerolled once
+    #[wasm_bindgen(js_name = rerollIfWorseThan)]
+    pub reroll_if_worse_than: BlockDieFace,
+}
+
+#[wasm_bindgen]
+impl BlockDiceOptions {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> BlockDiceOptions {
+        BlockDiceOptions {
+            num_dice: 2,
+            attacker_chooses: true,
+            has_reroll: false,
+            reroll_if_worse_than: BlockDieFace::Push,
+        }
+    }
+}
+
+// a block is never more than three dice, and 0 is rolled as 1
+impl RangedStats for BlockDiceOptions {
+    const STAT_RANGES: &'static [(&'static str, i32, i32)] = &[("numDice", 0, 3)];
+
+    fn stat_values(&self) -> Vec<i32> {
+        vec![self.num_dice]
+    }
+}
+
+#[wasm_bindgen(js_name = "blockDiceStatRanges")]
+pub fn block_dice_stat_ranges() -> StatRanges {
+    StatRanges::from_ranges(BlockDiceOptions::STAT_RANGES)
+}
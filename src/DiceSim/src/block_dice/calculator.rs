@@ -0,0 +1,116 @@
+use std::collections::HashMap;
+
+use wasm_bindgen::prelude::*;
+
+use super::block_dice_options::{BlockDiceOptions, BlockDieFace};
+use crate::common::calc_error::CalcError;
+use crate::common::stat_validation::RangedStats;
+use crate::common::ts_types::ToJsMap;
+
+const FACES: [BlockDieFace; 6] = [
+    BlockDieFace::AttackerDown,
+    BlockDieFace::BothDown,
+    BlockDieFace::Push,
+    BlockDieFace::Push,
+    BlockDieFace::DefenderStumbles,
+    BlockDieFace::DefenderDown,
+];
+const ALL_OUTCOMES: [BlockDieFace; 5] = [
+    BlockDieFace::AttackerDown,
+    BlockDieFace::BothDown,
+    BlockDieFace::Push,
+    BlockDieFace::DefenderStumbles,
+    BlockDieFace::DefenderDown,
+];
+
+// keys are BlockDieFace values
+#[wasm_bindgen(js_name = "blockDiceCalcOutcomeProbs")]
+pub fn block_dice_calc_outcome_probs(options: &BlockDiceOptions) -> Result<js_sys::Map, CalcError> {
+    options.validate_stats()?;
+    Ok(calc_outcome_probs(options)
+        .iter()
+        .map(|(face, prob)| (*face as i32, *prob))
+        .collect::<HashMap<i32, f64>>()
+        .to_js_map())
+}
+
+pub fn calc_outcome_probs(options: &BlockDiceOptions) -> HashMap<BlockDieFace, f64> {
+    let single_block_probs = calc_chosen_face_probs(options.num_dice, options.attacker_chooses);
+    if !options.has_reroll {
+        return single_block_probs;
+    }
+
+    let mut outcome_probs = HashMap::<BlockDieFace, f64>::new();
+    let reroll_prob: f64 = single_block_probs
+        .iter()
+        .filter(|(face, _)| **face < options.reroll_if_worse_than)
+        .map(|(_, prob)| prob)
+        .sum();
+    for (face, prob) in single_block_probs.iter() {
+        let kept_prob = if *face < options.reroll_if_worse_than {
+            0.0
+        } else {
+            *prob
+        };
+        outcome_probs.insert(*face, kept_prob + reroll_prob * prob);
+    }
+    outcome_probs
+}
+
+// with 0 dice we treat it as a single die, which is what a block always rolls at minimum
+fn calc_chosen_face_probs(num_dice: i32, attacker_chooses: bool) -> HashMap<BlockDieFace, f64> {
+    let num_dice = std::cmp::max(1, num_dice);
+    let mut chosen_probs = HashMap::<BlockDieFace, f64>::new();
+    let mut prev_cumulative_prob = 0.0;
+
+    for outcome in ALL_OUTCOMES {
+        // prob that chosen face is at most as good as this outcome
+        let cumulative_prob = if attacker_chooses {
+            face_prob_at_most(outcome).powi(num_dice)
+        } else {
+            1.0 - (1.0 - face_prob_at_most(outcome)).powi(num_dice)
+        };
+        chosen_probs.insert(outcome, cumulative_prob - prev_cumulative_prob);
+        prev_cumulative_prob = cumulative_prob;
+    }
+    chosen_probs
+}
+
+fn face_prob_at_most(outcome: BlockDieFace) -> f64 {
+    FACES.iter().filter(|face| **face <= outcome).count() as f64 / FACES.len() as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_prob(outcome_probs: &HashMap<BlockDieFace, f64>, face: BlockDieFace, expected: f64) {
+        assert!((outcome_probs[&face] - expected).abs() < 1e-12);
+    }
+
+    #[test]
+    fn two_dice_known_values() {
+        let mut options = BlockDiceOptions::new();
+        let outcome_probs = calc_outcome_probs(&options);
+        assert_prob(&outcome_probs, BlockDieFace::AttackerDown, 1.0 / 36.0);
+        assert_prob(&outcome_probs, BlockDieFace::DefenderDown, 11.0 / 36.0);
+
+        options.attacker_chooses = false;
+        let outcome_probs = calc_outcome_probs(&options);
+        assert_prob(&outcome_probs, BlockDieFace::AttackerDown, 11.0 / 36.0);
+        assert_prob(&outcome_probs, BlockDieFace::DefenderDown, 1.0 / 36.0);
+    }
+
+    // 0 dice rolls like 1, and the reroll only happens on a skull or both down
+    #[test]
+    fn reroll_of_worse_than_push() {
+        let mut options = BlockDiceOptions::new();
+        options.num_dice = 0;
+        options.has_reroll = true;
+        let outcome_probs = calc_outcome_probs(&options);
+        assert_prob(&outcome_probs, BlockDieFace::AttackerDown, 1.0 / 18.0);
+        assert_prob(&outcome_probs, BlockDieFace::Push, 1.0 / 3.0 + 1.0 / 9.0);
+        let total: f64 = outcome_probs.values().sum();
+        assert!((total - 1.0).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,7 @@
+mod block_dice_options;
+mod calculator;
+#[cfg(feature = "test-vectors")]
+mod test_vectors;
+
+#[cfg(feature = "test-vectors")]
+pub(crate) use test_vectors::test_vectors;
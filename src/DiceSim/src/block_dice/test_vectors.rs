@@ -0,0 +1,14 @@
+use super::block_dice_options::BlockDiceOptions;
+use super::calculator::calc_outcome_probs;
+use crate::test_vectors::flag_combo_test_vectors;
+
+flag_combo_test_vectors!(
+    "blockDice",
+    calc_outcome_probs {
+        options: BlockDiceOptions::new()
+    },
+    [
+        options.attacker_chooses = [false, true],
+        options.has_reroll = [false, true]
+    ],
+);
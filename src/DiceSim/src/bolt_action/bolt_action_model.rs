@@ -0,0 +1,60 @@
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+use crate::common::stat_validation::{RangedStats, MAX_COUNT_STAT};
+use crate::common::ts_types::StatRanges;
+
+#[wasm_bindgen]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BoltActionModel {
+    #[wasm_bindgen(js_name = numShots)]
+    pub num_shots: i32,
+    pub pen: i32, // added to the damage roll
+    #[wasm_bindgen(js_name = numPins)]
+    pub num_pins: i32, // each pin on the shooter is -1 to hit
+    #[wasm_bindgen(js_name = damageValue)]
+    pub damage_value: i32, // 3=inexperienced, 4=regular, 5=veteran
+    #[wasm_bindgen(js_name = numModels)]
+    pub num_models: i32, // can't lose more models than this
+}
+
+#[wasm_bindgen]
+impl BoltActionModel {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> BoltActionModel {
+        BoltActionModel {
+            num_shots: 5,
+            pen: 0,
+            num_pins: 0,
+            damage_value: 4,
+            num_models: 5,
+        }
+    }
+}
+
+// damage value only runs from inexperienced to veteran
+impl RangedStats for BoltActionModel {
+    const STAT_RANGES: &'static [(&'static str, i32, i32)] = &[
+        ("numShots", 0, MAX_COUNT_STAT),
+        ("pen", 0, MAX_COUNT_STAT),
+        ("numPins", 0, MAX_COUNT_STAT),
+        ("damageValue", 3, 5),
+        ("numModels", 1, MAX_COUNT_STAT),
+    ];
+
+    fn stat_values(&self) -> Vec<i32> {
+        vec![
+            self.num_shots,
+            self.pen,
+            self.num_pins,
+            self.damage_value,
+            self.num_models,
+        ]
+    }
+}
+
+#[wasm_bindgen(js_name = "boltActionStatRanges")]
+pub fn bolt_action_stat_ranges() -> StatRanges {
+    StatRanges::from_ranges(BoltActionModel::STAT_RANGES)
+}
@@ -0,0 +1,40 @@
+use serde::{Serialize, Serializer};
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum BoltActionCover {
+    None,
+    Soft, // -1 to hit
+    Hard, // -2 to hit
+}
+
+// as the number wasm-bindgen hands JS, rather than serde's variant name
+impl Serialize for BoltActionCover {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_i32(*self as i32)
+    }
+}
+
+#[wasm_bindgen]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BoltActionOptions {
+    pub cover: BoltActionCover,
+    #[wasm_bindgen(js_name = longRange)]
+    pub long_range: bool, // -1 to hit
+    #[wasm_bindgen(js_name = pointBlank)]
+    pub point_blank: bool, // +1 to hit
+}
+
+#[wasm_bindgen]
+impl BoltActionOptions {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> BoltActionOptions {
+        BoltActionOptions {
+            cover: BoltActionCover::None,
+            long_range: false,
+            point_blank: false,
+        }
+    }
+}
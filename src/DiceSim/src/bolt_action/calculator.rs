@@ -0,0 +1,138 @@
+use std::collections::HashMap;
+
+use wasm_bindgen::prelude::*;
+
+use super::bolt_action_model::BoltActionModel;
+use super::bolt_action_options::{BoltActionCover, BoltActionOptions};
+use crate::common::calc_error::CalcError;
+use crate::common::stat_validation::RangedStats;
+use crate::common::ts_types::ToJsMap;
+use crate::common::{add_to_map_value, binomial_pmf};
+
+const BASE_TO_HIT: i32 = 3;
+const DIE_SIDES: f64 = 6.0;
+
+#[wasm_bindgen(js_name = "boltActionCalcCasualtyProbs")]
+pub fn bolt_action_calc_casualty_probs(
+    shooter: &BoltActionModel,
+    target: &BoltActionModel,
+    options: &BoltActionOptions,
+) -> Result<js_sys::Map, CalcError> {
+    shooter.validate_stats()?;
+    target.validate_stats()?;
+    Ok(calc_casualty_probs(shooter, target, options).to_js_map())
+}
+
+// every shot is independent and can cause at most one casualty,
+// so casualties are binomial, capped by the number of models in the target
+pub fn calc_casualty_probs(
+    shooter: &BoltActionModel,
+    target: &BoltActionModel,
+    options: &BoltActionOptions,
+) -> HashMap<i32, f64> {
+    let mut casualty_probs = HashMap::<i32, f64>::new();
+    if shooter.num_shots <= 0 {
+        casualty_probs.insert(0, 1.0);
+        return casualty_probs;
+    }
+
+    let casualty_prob_per_shot = to_hit_prob(calc_to_hit_target(shooter, options))
+        * damage_prob(target.damage_value - shooter.pen);
+
+    for num_casualties in 0..=shooter.num_shots {
+        let capped_casualties = std::cmp::min(num_casualties, target.num_models);
+        add_to_map_value(
+            &mut casualty_probs,
+            &capped_casualties,
+            binomial_pmf(shooter.num_shots, num_casualties, casualty_prob_per_shot),
+        );
+    }
+    casualty_probs
+}
+
+// the d6 result needed to hit, which can go beyond 6
+fn calc_to_hit_target(shooter: &BoltActionModel, options: &BoltActionOptions) -> i32 {
+    let mut hit_mod = -shooter.num_pins;
+    hit_mod -= match options.cover {
+        BoltActionCover::None => 0,
+        BoltActionCover::Soft => 1,
+        BoltActionCover::Hard => 2,
+    };
+    if options.long_range {
+        hit_mod -= 1;
+    }
+    if options.point_blank {
+        hit_mod += 1;
+    }
+    BASE_TO_HIT - hit_mod
+}
+
+// a natural 1 always misses; needing 7+ means a 6 followed by a 4+, 8+ is 6 then 5+, 9+ is 6 then 6+
+fn to_hit_prob(to_hit_target: i32) -> f64 {
+    match to_hit_target {
+        i32::MIN..=2 => 5.0 / DIE_SIDES,
+        3..=6 => (7 - to_hit_target) as f64 / DIE_SIDES,
+        7..=9 => (10 - to_hit_target) as f64 / (DIE_SIDES * DIE_SIDES),
+        _ => 0.0,
+    }
+}
+
+// a natural 1 always fails to damage and a natural 6 always succeeds
+fn damage_prob(damage_target: i32) -> f64 {
+    let clamped_target = damage_target.clamp(2, 6);
+    (7 - clamped_target) as f64 / DIE_SIDES
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::stat_validation::MAX_COUNT_STAT;
+
+    #[test]
+    fn to_hit_prob_needs_a_six_then_more_past_six() {
+        assert_eq!(to_hit_prob(1), 5.0 / 6.0);
+        assert_eq!(to_hit_prob(3), 4.0 / 6.0);
+        assert_eq!(to_hit_prob(7), 3.0 / 36.0);
+        assert_eq!(to_hit_prob(9), 1.0 / 36.0);
+        assert_eq!(to_hit_prob(10), 0.0);
+    }
+
+    #[test]
+    fn one_shot_casualty_known_value() {
+        let mut shooter = BoltActionModel::new();
+        shooter.num_shots = 1;
+        let target = BoltActionModel::new();
+        let mut options = BoltActionOptions::new();
+        options.cover = BoltActionCover::Soft;
+        // hits on 4+, then damages a regular target on 4+
+        let casualty_probs = calc_casualty_probs(&shooter, &target, &options);
+        assert!((casualty_probs[&1] - 0.25).abs() < 1e-12);
+        assert!((casualty_probs[&0] - 0.75).abs() < 1e-12);
+    }
+
+    #[test]
+    fn more_shots_than_the_n_choose_k_table_has() {
+        let mut shooter = BoltActionModel::new();
+        shooter.num_shots = 40;
+        let mut target = BoltActionModel::new();
+        target.num_models = 40;
+        let casualty_probs = calc_casualty_probs(&shooter, &target, &BoltActionOptions::new());
+        let total: f64 = casualty_probs.values().sum();
+        assert!((total - 1.0).abs() < 1e-12);
+
+        shooter.num_shots = MAX_COUNT_STAT + 1;
+        assert_eq!(shooter.validate_stats(), Err(CalcError::InvalidStat));
+    }
+
+    #[test]
+    fn casualties_capped_by_num_models() {
+        let mut shooter = BoltActionModel::new();
+        shooter.num_shots = 6;
+        let mut target = BoltActionModel::new();
+        target.num_models = 2;
+        let casualty_probs = calc_casualty_probs(&shooter, &target, &BoltActionOptions::new());
+        assert_eq!(casualty_probs.keys().max(), Some(&2));
+        let total: f64 = casualty_probs.values().sum();
+        assert!((total - 1.0).abs() < 1e-12);
+    }
+}
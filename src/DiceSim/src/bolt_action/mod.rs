@@ -0,0 +1,8 @@
+mod bolt_action_model;
+mod bolt_action_options;
+mod calculator;
+#[cfg(feature = "test-vectors")]
+mod test_vectors;
+
+#[cfg(feature = "test-vectors")]
+pub(crate) use test_vectors::test_vectors;
@@ -0,0 +1,22 @@
+use super::bolt_action_model::BoltActionModel;
+use super::bolt_action_options::{BoltActionCover, BoltActionOptions};
+use super::calculator::calc_casualty_probs;
+use crate::test_vectors::flag_combo_test_vectors;
+
+flag_combo_test_vectors!(
+    "boltAction",
+    calc_casualty_probs {
+        shooter: BoltActionModel::new(),
+        target: BoltActionModel::new(),
+        options: BoltActionOptions::new(),
+    },
+    [
+        options.long_range = [false, true],
+        options.point_blank = [false, true],
+        options.cover = [
+            BoltActionCover::None,
+            BoltActionCover::Soft,
+            BoltActionCover::Hard
+        ],
+    ],
+);
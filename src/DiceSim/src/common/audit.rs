@@ -0,0 +1,56 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
+use wasm_bindgen::prelude::*;
+
+use super::ts_types::{js_map_to_probs, result_schema_v1, RESULT_SCHEMA_VERSION};
+
+// a normalized distribution's total mass is within this of 1, allowing for float rounding
+const MASS_TOLERANCE: f64 = 1e-9;
+
+// sanity numbers for a returned distribution; a well-formed one has total mass 1
+// and no negative or non-finite probs
+#[derive(Tsify, Serialize, Deserialize)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+#[serde(rename_all = "camelCase")]
+pub struct ProbsAudit {
+    #[serde(default = "result_schema_v1")]
+    pub result_schema_version: u32,
+    pub total_mass: f64,
+    pub is_normalized: bool,
+    pub min_value: Option<i32>,
+    pub max_value: Option<i32>,
+    pub num_buckets: usize,
+    pub num_bad_probs: usize, // negative, NaN or infinite
+    pub entropy_bits: f64,
+}
+
+impl ProbsAudit {
+    pub fn from_probs(probs: &HashMap<i32, f64>) -> ProbsAudit {
+        let total_mass: f64 = probs.values().sum();
+        ProbsAudit {
+            result_schema_version: RESULT_SCHEMA_VERSION,
+            total_mass,
+            is_normalized: (total_mass - 1.0).abs() <= MASS_TOLERANCE,
+            min_value: probs.keys().min().copied(),
+            max_value: probs.keys().max().copied(),
+            num_buckets: probs.len(),
+            num_bad_probs: probs
+                .values()
+                .filter(|prob| !prob.is_finite() || **prob < 0.0)
+                .count(),
+            entropy_bits: -probs
+                .values()
+                .filter(|prob| prob.is_finite() && **prob > 0.0)
+                .map(|prob| prob * prob.log2())
+                .sum::<f64>(),
+        }
+    }
+}
+
+// works on any Map<number, number> that a calc function returned
+#[wasm_bindgen(js_name = "auditProbs")]
+pub fn audit_probs(probs: &js_sys::Map) -> ProbsAudit {
+    ProbsAudit::from_probs(&js_map_to_probs(probs))
+}
@@ -0,0 +1,13 @@
+use wasm_bindgen::prelude::*;
+
+// thrown to JS as the enum's number, so the UI can branch on `e === CalcError.InvalidStat`
+// instead of parsing panic messages
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CalcError {
+    InvalidStat,    // a stat or option is outside what the rules allow, like negative dice
+    Overflow,       // inputs so big that intermediate counts wouldn't fit
+    Unsupported,    // valid, but beyond what the calculator handles, like replaying exact calcs
+    BudgetExceeded, // would take too long, like billions of simulated dice
+    InvalidJson,    // saved json that doesn't parse as what it's loaded as
+}
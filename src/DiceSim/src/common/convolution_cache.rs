@@ -0,0 +1,66 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use wasm_bindgen::prelude::*;
+
+use super::convolve_probs;
+
+// each entry is a whole distribution, so keep the cache from growing without bound
+const MAX_CACHED_POWERS: usize = 256;
+
+// (value, f64 bits of its prob) sorted by value, since f64 and HashMap aren't Hash
+type ProbsKey = Vec<(i32, u64)>;
+
+thread_local! {
+    // (base distribution, power) -> base distribution convolved with itself power times;
+    // keyed by the distribution itself, so two distributions can never share an entry
+    static CONVOLUTION_POWERS: RefCell<HashMap<(ProbsKey, i32), HashMap<i32, f64>>> =
+        RefCell::new(HashMap::new());
+}
+
+#[wasm_bindgen(js_name = "clearConvolutionCache")]
+pub fn clear_convolution_cache() {
+    CONVOLUTION_POWERS.with(|cache| cache.borrow_mut().clear());
+}
+
+// distribution of the sum of power independent copies of probs;
+// reuses any powers cached by earlier calls with the same distribution
+pub fn calc_convolution_power(probs: &HashMap<i32, f64>, power: i32) -> HashMap<i32, f64> {
+    calc_convolution_powers(probs, power).pop().unwrap()
+}
+
+// element i is the distribution of the sum of i+1 independent copies of probs
+pub fn calc_convolution_powers(
+    probs: &HashMap<i32, f64>,
+    max_power: i32,
+) -> Vec<HashMap<i32, f64>> {
+    let key = probs_key(probs);
+    let mut powers = vec![probs.clone()];
+
+    for power in 2..=max_power {
+        let cached_power =
+            CONVOLUTION_POWERS.with(|cache| cache.borrow().get(&(key.clone(), power)).cloned());
+        let next_power = match cached_power {
+            Some(cached_power) => cached_power,
+            None => {
+                let next_power = convolve_probs(powers.last().unwrap(), probs);
+                CONVOLUTION_POWERS.with(|cache| {
+                    let mut cache = cache.borrow_mut();
+                    if cache.len() >= MAX_CACHED_POWERS {
+                        cache.clear();
+                    }
+                    cache.insert((key.clone(), power), next_power.clone());
+                });
+                next_power
+            }
+        };
+        powers.push(next_power);
+    }
+    powers
+}
+
+fn probs_key(probs: &HashMap<i32, f64>) -> ProbsKey {
+    let mut sorted_probs: ProbsKey = probs.iter().map(|(k, v)| (*k, v.to_bits())).collect();
+    sorted_probs.sort_unstable();
+    sorted_probs
+}
@@ -0,0 +1,88 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use wasm_bindgen::prelude::*;
+
+use super::ts_types::ToJsMap;
+
+// one row per outcome, keyed like "blockDice.Push" for enum values or "deadzone.fight.bothDie" for
+// result fields, and one column per locale; an empty cell falls back to English
+const OUTCOME_LABELS_TSV: &str = include_str!("outcome_labels.tsv");
+const FALLBACK_LOCALE: &str = "en";
+
+struct LabelTable {
+    locales: Vec<&'static str>,
+    labels_by_key: HashMap<&'static str, Vec<&'static str>>,
+}
+
+thread_local! {
+    static LABEL_TABLE: RefCell<Option<LabelTable>> = const { RefCell::new(None) };
+}
+
+// display string for an outcome, or the key itself if there's no row for it;
+// a locale like "es-MX" falls back to "es" and then to English
+#[wasm_bindgen(js_name = "outcomeLabel")]
+pub fn outcome_label(key: &str, locale: &str) -> String {
+    with_label_table(|table| {
+        table
+            .labels_by_key
+            .get(key)
+            .map(|labels| pick_label(table, labels, locale))
+            .unwrap_or(key)
+            .to_string()
+    })
+}
+
+// every outcome's display string, keyed like outcomeLabel's keys, for UIs that label many results at once
+#[wasm_bindgen(js_name = "outcomeLabels")]
+pub fn outcome_labels(locale: &str) -> js_sys::Map {
+    with_label_table(|table| {
+        table
+            .labels_by_key
+            .iter()
+            .map(|(key, labels)| (*key, pick_label(table, labels, locale)))
+            .collect::<HashMap<&str, &str>>()
+            .to_js_map()
+    })
+}
+
+fn with_label_table<T>(f: impl FnOnce(&LabelTable) -> T) -> T {
+    LABEL_TABLE.with(|table| {
+        f(table
+            .borrow_mut()
+            .get_or_insert_with(|| parse_label_table(OUTCOME_LABELS_TSV)))
+    })
+}
+
+fn parse_label_table(tsv: &'static str) -> LabelTable {
+    let mut lines = tsv.lines().filter(|line| !line.trim().is_empty());
+    let locales = lines
+        .next()
+        .map(|header| header.split('\t').skip(1).collect())
+        .unwrap_or_default();
+    let labels_by_key = lines
+        .map(|line| {
+            let mut cells = line.split('\t');
+            (cells.next().unwrap(), cells.collect())
+        })
+        .collect();
+    LabelTable {
+        locales,
+        labels_by_key,
+    }
+}
+
+fn pick_label(table: &LabelTable, labels: &[&'static str], locale: &str) -> &'static str {
+    let language = locale.split(['-', '_']).next().unwrap_or(locale);
+    [locale, language, FALLBACK_LOCALE]
+        .iter()
+        .filter_map(|wanted| {
+            table
+                .locales
+                .iter()
+                .position(|table_locale| table_locale.eq_ignore_ascii_case(wanted))
+        })
+        .filter_map(|col| labels.get(col).copied())
+        .find(|label| !label.is_empty())
+        .unwrap_or("")
+}
@@ -1,9 +1,20 @@
 use core::hash::Hash;
 use num::traits::NumAssignRef;
 use std::collections::HashMap;
+use wasm_bindgen::prelude::*;
 
+pub mod audit;
+pub mod calc_error;
+pub mod convolution_cache;
+pub mod localization;
+pub mod normalizer;
+pub mod reduction;
+pub mod stat_validation;
 pub mod ts_types;
 
+use calc_error::CalcError;
+use convolution_cache::{calc_convolution_power, calc_convolution_powers};
+
 pub fn add_to_map_value<KeyType: Eq + Hash + Copy, ValType: NumAssignRef>(
     map: &mut HashMap<KeyType, ValType>,
     key: &KeyType,
@@ -12,21 +23,93 @@ pub fn add_to_map_value<KeyType: Eq + Hash + Copy, ValType: NumAssignRef>(
     *map.entry(*key).or_insert(ValType::zero()) += val;
 }
 
-#[allow(dead_code)]
-pub fn normalize_map_values<KeyType: Eq + Hash + Copy, ValType: std::ops::DivAssign + Copy>(
-    map: &mut HashMap<KeyType, ValType>,
-    divisor: ValType,
-) {
-    for (_key, val) in map.iter_mut() {
-        *val /= divisor;
-    }
-}
-
 pub fn binomial_pmf(num_trials: i32, num_successes: i32, prob_success: f64) -> f64 {
     // often the variables are named numTrials=n, numSuccesses=k, probSuccess=p
-    return n_choose_k(num_trials, num_successes) as f64
+    if num_trials > MAX_N_CHOOSE_K {
+        return large_binomial_pmf(num_trials, num_successes, prob_success);
+    }
+    n_choose_k(num_trials, num_successes) as f64
         * prob_success.powf(num_successes.into())
-        * (1.0 - prob_success).powf((num_trials - num_successes).into());
+        * (1.0 - prob_success).powf((num_trials - num_successes).into())
+}
+
+// for more trials than n_choose_k's table has, worked out in log space so nothing overflows
+fn large_binomial_pmf(num_trials: i32, num_successes: i32, prob_success: f64) -> f64 {
+    if num_successes < 0 || num_successes > num_trials {
+        return 0.0;
+    }
+    let num_failures = num_trials - num_successes;
+    // a factor with no trials is 1 even when its prob is 0, but 0 * ln(0) would be NaN
+    let ln_factor = |num: i32, prob: f64| {
+        if num == 0 {
+            0.0
+        } else {
+            num as f64 * prob.ln()
+        }
+    };
+    (ln_n_choose_k(num_trials, num_successes)
+        + ln_factor(num_successes, prob_success)
+        + ln_factor(num_failures, 1.0 - prob_success))
+    .exp()
+}
+
+// ln(n choose k) as a sum of ln((n-s+i)/i) for i in 1..=s, with s=min(k, n-k), for 0 <= k <= n
+fn ln_n_choose_k(n: i32, k: i32) -> f64 {
+    let s = std::cmp::min(k, n - k);
+    (1..=s).map(|i| ((n - s + i) as f64 / i as f64).ln()).sum()
+}
+
+// draws without replacement, like limited reroll tokens or cards drawn from a deck, for engines on
+// either side of the wasm boundary; often the variables are named populationSize=N,
+// numSuccessStates=K, numDraws=n, numObservedSuccesses=k
+#[wasm_bindgen(js_name = "hypergeometricPmf")]
+pub fn hypergeometric_pmf(
+    population_size: i32,
+    num_success_states: i32,
+    num_draws: i32,
+    num_observed_successes: i32,
+) -> Result<f64, CalcError> {
+    if population_size < 0
+        || !(0..=population_size).contains(&num_success_states)
+        || !(0..=population_size).contains(&num_draws)
+    {
+        return Err(CalcError::InvalidStat);
+    }
+    let num_failure_states = population_size - num_success_states;
+    let num_observed_failures = num_draws - num_observed_successes;
+    if !(0..=num_success_states).contains(&num_observed_successes)
+        || !(0..=num_failure_states).contains(&num_observed_failures)
+    {
+        return Ok(0.0);
+    }
+    if population_size <= MAX_N_CHOOSE_K {
+        return Ok(
+            n_choose_k(num_success_states, num_observed_successes) as f64
+                * n_choose_k(num_failure_states, num_observed_failures) as f64
+                / n_choose_k(population_size, num_draws) as f64,
+        );
+    }
+    Ok((ln_n_choose_k(num_success_states, num_observed_successes)
+        + ln_n_choose_k(num_failure_states, num_observed_failures)
+        - ln_n_choose_k(population_size, num_draws))
+    .exp())
+}
+
+// number of failures before the first success
+pub fn geometric_pmf(num_failures: i32, prob_success: f64) -> f64 {
+    (1.0 - prob_success).powi(num_failures) * prob_success
+}
+
+// number of extra dice a single exploding die generates, where each roll explodes with
+// prob_explode and the chain is cut off after max_explosions extra dice
+pub fn explosion_chain_pmf(num_explosions: i32, prob_explode: f64, max_explosions: i32) -> f64 {
+    if num_explosions < 0 || num_explosions > max_explosions {
+        0.0
+    } else if num_explosions == max_explosions {
+        prob_explode.powi(max_explosions)
+    } else {
+        geometric_pmf(num_explosions, 1.0 - prob_explode)
+    }
 }
 
 // can only handle up to num_trials=29 (29*28*..*16 < max_i64 < 30*29*..*16)
@@ -38,9 +121,22 @@ pub fn binomial_pmf(num_trials: i32, num_successes: i32, prob_success: f64) -> f
 // - https://docs.rs/num-integer/latest/num_integer/fn.binomial.html
 //   - does the recursive implementation; probably roughly same number of operations as above
 //
+// biggest n that n_choose_k's lookup table handles; C(30, 15) itself is only about 1.55e8, but
+// the product 30*29*..*16 that it divides down to get there overflows i64
+pub const MAX_N_CHOOSE_K: i32 = 29;
+
 pub fn n_choose_k(n: i32, k: i32) -> i64 {
     // often the variables are named numTrials=n, numSuccesses=k
-    const MAX_NUM_TRIALS: usize = 29;
+    const MAX_NUM_TRIALS: usize = MAX_N_CHOOSE_K as usize;
+
+    // handle the trivial cases without the table, which has no row for n=0
+    if k < 0 || k > n {
+        return 0;
+    }
+    if k == 0 || k == n {
+        return 1;
+    }
+
     static mut LOOKUP_TABLE: [[i64; MAX_NUM_TRIALS]; MAX_NUM_TRIALS + 1] =
         [[0; MAX_NUM_TRIALS]; MAX_NUM_TRIALS + 1];
 
@@ -78,28 +174,313 @@ pub fn n_choose_k(n: i32, k: i32) -> i64 {
     result
 }
 
+// distribution of the sum of two independent random variables
+pub fn convolve_probs(
+    probs_a: &HashMap<i32, f64>,
+    probs_b: &HashMap<i32, f64>,
+) -> HashMap<i32, f64> {
+    let mut sum_probs = HashMap::<i32, f64>::new();
+    for (val_a, prob_a) in probs_a.iter() {
+        for (val_b, prob_b) in probs_b.iter() {
+            add_to_map_value(&mut sum_probs, &(val_a + val_b), prob_a * prob_b);
+        }
+    }
+    sum_probs
+}
+
+// distribution of -x, which turns convolution into subtraction
+pub fn negate_probs(probs: &HashMap<i32, f64>) -> HashMap<i32, f64> {
+    probs.iter().map(|(val, prob)| (-val, *prob)).collect()
+}
+
+// distribution of a - b for independent a and b, like the net successes of an opposed roll
+pub fn difference_probs(
+    probs_a: &HashMap<i32, f64>,
+    probs_b: &HashMap<i32, f64>,
+) -> HashMap<i32, f64> {
+    convolve_probs(probs_a, &negate_probs(probs_b))
+}
+
+// distribution of max(a - b, 0), like an opposed roll where only one side can come out ahead
+pub fn positive_difference_probs(
+    probs_a: &HashMap<i32, f64>,
+    probs_b: &HashMap<i32, f64>,
+) -> HashMap<i32, f64> {
+    let mut clamped_probs = HashMap::<i32, f64>::new();
+    for (val, prob) in difference_probs(probs_a, probs_b).iter() {
+        add_to_map_value(&mut clamped_probs, &std::cmp::max(0, *val), *prob);
+    }
+    clamped_probs
+}
+
+// for display and smaller payloads: buckets under min_prob are dropped and the rest renormalized,
+// then probs are rounded to num_decimals, dropping any that round to 0; i32::MAX decimals means no rounding
+pub fn tidy_probs<T: Copy + Eq + Hash>(
+    probs: &HashMap<T, f64>,
+    min_prob: f64,
+    num_decimals: i32,
+) -> HashMap<T, f64> {
+    // only renormalized when something was dropped, so untouched probs come back exactly as they were
+    let kept_mass: f64 = if probs.values().any(|prob| *prob < min_prob) {
+        probs.values().filter(|prob| **prob >= min_prob).sum()
+    } else {
+        1.0
+    };
+    if kept_mass <= 0.0 {
+        return HashMap::new();
+    }
+    // f64 can't hold more than 17 significant digits anyway
+    let scale = (num_decimals < 17).then(|| 10f64.powi(num_decimals));
+    probs
+        .iter()
+        .filter(|(_, prob)| **prob >= min_prob)
+        .map(|(val, prob)| {
+            let prob = prob / kept_mass;
+            (
+                *val,
+                scale.map_or(prob, |scale| (prob * scale).round() / scale),
+            )
+        })
+        .filter(|(_, prob)| *prob > 0.0)
+        .collect()
+}
+
 pub fn calc_multi_round_damage(
     single_round_dmg_probs: &HashMap<i32, f64>,
     num_rounds: i32,
 ) -> HashMap<i32, f64> {
-    let mut latest_round_dmg_probs = single_round_dmg_probs.clone();
-    let mut prev_round_dmg_probs = HashMap::<i32, f64>::new();
-
-    for _round_number in 2..=num_rounds {
-        // swap, then clear the "frontier" map
-        [prev_round_dmg_probs, latest_round_dmg_probs] =
-            [latest_round_dmg_probs, prev_round_dmg_probs];
-        latest_round_dmg_probs.clear();
-
-        for (prev_rounds_dmg, prev_rounds_prob) in prev_round_dmg_probs.iter() {
-            for (single_round_dmg, single_round_prob) in single_round_dmg_probs.iter() {
-                add_to_map_value(
-                    &mut latest_round_dmg_probs,
-                    &(prev_rounds_dmg + single_round_dmg),
-                    prev_rounds_prob * single_round_prob,
-                );
-            }
+    calc_convolution_power(single_round_dmg_probs, num_rounds)
+}
+
+// element i is the cumulative damage distribution after round i+1
+pub fn calc_multi_round_damage_by_round(
+    single_round_dmg_probs: &HashMap<i32, f64>,
+    num_rounds: i32,
+) -> Vec<HashMap<i32, f64>> {
+    calc_convolution_powers(single_round_dmg_probs, num_rounds)
+}
+
+pub fn expected_value(probs: &HashMap<i32, f64>) -> f64 {
+    probs.iter().map(|(val, prob)| *val as f64 * prob).sum()
+}
+
+pub fn std_dev(probs: &HashMap<i32, f64>) -> f64 {
+    let mean = expected_value(probs);
+    probs
+        .iter()
+        .map(|(val, prob)| (*val as f64 - mean).powi(2) * prob)
+        .sum::<f64>()
+        .sqrt()
+}
+
+pub fn prob_at_least(probs: &HashMap<i32, f64>, threshold: i32) -> f64 {
+    probs
+        .iter()
+        .filter(|(val, _)| **val >= threshold)
+        .map(|(_, prob)| prob)
+        .sum()
+}
+
+// milliseconds since an arbitrary start point, for timing phases of a calculation
+#[cfg(target_arch = "wasm32")]
+pub fn now_ms() -> f64 {
+    use wasm_bindgen::JsCast;
+    // prefer performance.now() for sub-millisecond resolution, but workers and old browsers may lack it
+    let performance = js_sys::Reflect::get(&js_sys::global(), &"performance".into());
+    performance
+        .ok()
+        .and_then(|performance| {
+            let now_fn = js_sys::Reflect::get(&performance, &"now".into()).ok()?;
+            now_fn
+                .dyn_into::<js_sys::Function>()
+                .ok()?
+                .call0(&performance)
+                .ok()?
+                .as_f64()
+        })
+        .unwrap_or_else(js_sys::Date::now)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn now_ms() -> f64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0.0, |elapsed| elapsed.as_secs_f64() * 1000.0)
+}
+
+// expected damage beyond what was needed to take the target from hp to zero
+pub fn expected_overkill(dmg_probs: &HashMap<i32, f64>, hp: i32) -> f64 {
+    dmg_probs
+        .iter()
+        .filter(|(dmg, _)| **dmg > hp)
+        .map(|(dmg, prob)| (dmg - hp) as f64 * prob)
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn n_choose_k_edges() {
+        assert_eq!(n_choose_k(0, 0), 1);
+        assert_eq!(n_choose_k(5, 0), 1);
+        assert_eq!(n_choose_k(5, 5), 1);
+        assert_eq!(n_choose_k(5, 6), 0);
+        assert_eq!(n_choose_k(5, -1), 0);
+        assert_eq!(n_choose_k(5, 2), 10);
+        assert_eq!(n_choose_k(29, 14), 77558760);
+    }
+
+    #[test]
+    fn binomial_pmf_sums_to_one() {
+        let total: f64 = (0..=10).map(|k| binomial_pmf(10, k, 0.375)).sum();
+        assert!((total - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn binomial_pmf_past_the_n_choose_k_table() {
+        // log space agrees with the table where both work
+        for k in 0..=MAX_N_CHOOSE_K {
+            let table_prob = binomial_pmf(MAX_N_CHOOSE_K, k, 0.375);
+            let log_space_prob = large_binomial_pmf(MAX_N_CHOOSE_K, k, 0.375);
+            assert!((table_prob - log_space_prob).abs() < 1e-12 * table_prob.max(1e-300));
         }
+        let total: f64 = (0..=100).map(|k| binomial_pmf(100, k, 0.375)).sum();
+        assert!((total - 1.0).abs() < 1e-12);
+        assert_eq!(binomial_pmf(40, 0, 0.0), 1.0);
+        assert_eq!(binomial_pmf(40, 40, 1.0), 1.0);
+        assert_eq!(binomial_pmf(40, 41, 0.5), 0.0);
+    }
+
+    #[test]
+    fn hypergeometric_pmf_sums_to_one_and_matches_known_value() {
+        for population_size in [20, 60] {
+            let total: f64 = (0..=5)
+                .map(|k| hypergeometric_pmf(population_size, 7, 5, k).unwrap())
+                .sum();
+            assert!((total - 1.0).abs() < 1e-12);
+        }
+        // C(4,2) * C(6,1) / C(10,3)
+        assert!((hypergeometric_pmf(10, 4, 3, 2).unwrap() - 36.0 / 120.0).abs() < 1e-12);
+        // C(30,2) * C(10,1) / C(40,3), past n_choose_k's table
+        assert!((hypergeometric_pmf(40, 30, 3, 2).unwrap() - 4350.0 / 9880.0).abs() < 1e-12);
+        assert_eq!(hypergeometric_pmf(10, 4, 3, 4), Ok(0.0));
+        assert_eq!(
+            hypergeometric_pmf(10, 11, 3, 2),
+            Err(CalcError::InvalidStat)
+        );
+    }
+
+    #[test]
+    fn explosion_chain_pmf_sums_to_one() {
+        let chain_total: f64 = (0..=4).map(|e| explosion_chain_pmf(e, 0.125, 4)).sum();
+        assert!((chain_total - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn outcome_labels_fall_back_by_locale() {
+        use localization::outcome_label;
+        assert_eq!(outcome_label("blockDice.Push", "es-MX"), "Empujón");
+        assert_eq!(outcome_label("blockDice.Push", "fr"), "Push");
+        assert_eq!(outcome_label("no.such.outcome", "en"), "no.such.outcome");
+    }
+
+    #[test]
+    fn normalized_metrics_ignore_overkill_and_negative_dmg() {
+        use normalizer::NormalizedMetrics;
+        let dmg_probs = HashMap::from([(-1, 0.25), (0, 0.25), (1, 0.25), (5, 0.25)]);
+        let metrics = NormalizedMetrics::from_dmg_probs(&dmg_probs, 2).unwrap();
+        assert_eq!(metrics.kill_prob, 0.25);
+        assert_eq!(metrics.any_dmg_prob, 0.5);
+        assert!((metrics.expected_wounds_fraction - 0.375).abs() < 1e-12);
+        assert!(NormalizedMetrics::from_dmg_probs(&dmg_probs, 0).is_err());
+    }
+
+    #[test]
+    fn tidy_probs_drops_renormalizes_and_rounds() {
+        let probs = HashMap::from([(0, 0.5), (1, 0.29995), (2, 0.2), (3, 0.00005)]);
+        assert_eq!(tidy_probs(&probs, 0.0, i32::MAX), probs);
+        let tidied = tidy_probs(&probs, 0.0001, 4);
+        assert_eq!(tidied, HashMap::from([(0, 0.5), (1, 0.3), (2, 0.2)]));
+    }
+
+    #[test]
+    fn partial_probs_merge_by_weight_with_compensation() {
+        use reduction::{compensated_sum, merge_partial_probs};
+        assert_eq!(compensated_sum(&[1.0, 1e100, 1.0, -1e100]), 2.0);
+        let merged = merge_partial_probs(&[
+            (HashMap::from([(0, 1.0)]), 1.0),
+            (HashMap::from([(0, 0.5), (1, 0.5)]), 3.0),
+        ]);
+        assert_eq!(merged, HashMap::from([(0, 0.625), (1, 0.375)]));
+    }
+
+    #[test]
+    fn difference_probs_subtract_and_clamp() {
+        let probs_a = HashMap::from([(1, 0.5), (3, 0.5)]);
+        let probs_b = HashMap::from([(0, 0.25), (2, 0.75)]);
+        assert_eq!(
+            difference_probs(&probs_a, &probs_b),
+            HashMap::from([(-1, 0.375), (1, 0.5), (3, 0.125)])
+        );
+        assert_eq!(
+            positive_difference_probs(&probs_a, &probs_b),
+            HashMap::from([(0, 0.375), (1, 0.5), (3, 0.125)])
+        );
+    }
+
+    #[test]
+    fn compressed_probs_merges_tails_and_runs() {
+        let probs = HashMap::from([
+            (-3, 1e-12),
+            (0, 0.25),
+            (1, 0.5),
+            (3, 0.25 - 2e-12),
+            (9, 1e-12),
+        ]);
+        let compressed = ts_types::CompressedProbs::from_probs(&probs, 1e-9, true);
+        assert_eq!(compressed.runs.len(), 2);
+        assert_eq!(compressed.runs[0].start, 0);
+        assert_eq!(compressed.runs[0].probs.len(), 2);
+        assert_eq!((compressed.below_value, compressed.above_value), (-1, 4));
+        let decompressed = compressed.to_probs();
+        assert!((decompressed.values().sum::<f64>() - 1.0).abs() < 1e-12);
+        assert_eq!(decompressed[&1], 0.5);
+
+        let uncompressed_runs = ts_types::CompressedProbs::from_probs(&probs, 1e-9, false);
+        assert_eq!(uncompressed_runs.runs.len(), 3);
+    }
+
+    #[test]
+    fn v1_results_still_deserialize() {
+        let top_k: ts_types::TopKProbs =
+            serde_json::from_str(r#"{"top":[{"value":1,"prob":0.75}],"otherProb":0.25}"#).unwrap();
+        assert_eq!(top_k.result_schema_version, 1);
+        assert_eq!(top_k.other_prob, 0.25);
+
+        let v1_items = ts_types::deserialize_versioned_items::<_, i32>(
+            &mut serde_json::Deserializer::from_str("[3, 4]"),
+        )
+        .unwrap();
+        assert_eq!(v1_items, (1, vec![3, 4]));
+        let v2_items = ts_types::deserialize_versioned_items::<_, i32>(
+            &mut serde_json::Deserializer::from_str(r#"{"resultSchemaVersion":2,"items":[3]}"#),
+        )
+        .unwrap();
+        assert_eq!(v2_items, (2, vec![3]));
+    }
+
+    #[test]
+    fn probs_audit_reports_mass_range_and_entropy() {
+        let audit =
+            audit::ProbsAudit::from_probs(&HashMap::from([(-1, 0.25), (2, 0.25), (3, 0.5)]));
+        assert!(audit.is_normalized);
+        assert_eq!((audit.min_value, audit.max_value), (Some(-1), Some(3)));
+        assert!((audit.entropy_bits - 1.5).abs() < 1e-12);
+
+        let bad_audit = audit::ProbsAudit::from_probs(&HashMap::from([(0, 0.7), (1, -0.1)]));
+        assert!(!bad_audit.is_normalized);
+        assert_eq!(bad_audit.num_bad_probs, 1);
     }
-    return latest_round_dmg_probs;
 }
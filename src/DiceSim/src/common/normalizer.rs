@@ -0,0 +1,54 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
+use wasm_bindgen::prelude::*;
+
+use super::ts_types::{js_map_to_probs, result_schema_v1, RESULT_SCHEMA_VERSION};
+use super::{calc_error::CalcError, prob_at_least};
+
+// game-independent numbers for one attack against a reference target, so profiles from
+// different games can be put side by side; dmg is in whatever unit the target's wounds are in,
+// and negative dmg (dealt back to the attacker) counts as none
+#[derive(Tsify, Serialize, Deserialize)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+#[serde(rename_all = "camelCase")]
+pub struct NormalizedMetrics {
+    #[serde(default = "result_schema_v1")]
+    pub result_schema_version: u32,
+    pub target_wounds: i32,
+    pub kill_prob: f64,
+    pub expected_wounds_fraction: f64, // expected share of the target's wounds removed, overkill excluded
+    pub any_dmg_prob: f64,
+}
+
+impl NormalizedMetrics {
+    pub fn from_dmg_probs(
+        dmg_probs: &HashMap<i32, f64>,
+        target_wounds: i32,
+    ) -> Result<NormalizedMetrics, CalcError> {
+        if target_wounds < 1 {
+            return Err(CalcError::InvalidStat);
+        }
+        let expected_wounds_removed: f64 = dmg_probs
+            .iter()
+            .map(|(dmg, prob)| (*dmg).clamp(0, target_wounds) as f64 * prob)
+            .sum();
+        Ok(NormalizedMetrics {
+            result_schema_version: RESULT_SCHEMA_VERSION,
+            target_wounds,
+            kill_prob: prob_at_least(dmg_probs, target_wounds),
+            expected_wounds_fraction: expected_wounds_removed / target_wounds as f64,
+            any_dmg_prob: prob_at_least(dmg_probs, 1),
+        })
+    }
+}
+
+// for dmg probs from any engine, including the KT one that lives on the TS side
+#[wasm_bindgen(js_name = "normalizeDmgProbs")]
+pub fn normalize_dmg_probs(
+    dmg_probs: &js_sys::Map,
+    target_wounds: i32,
+) -> Result<NormalizedMetrics, CalcError> {
+    NormalizedMetrics::from_dmg_probs(&js_map_to_probs(dmg_probs), target_wounds)
+}
@@ -0,0 +1,55 @@
+use std::collections::{BTreeSet, HashMap};
+
+// merging of partial distributions, like parallel chunks of one calculation or the cases of a mixture;
+// results only depend on the order of the partials, so workers should hand them over by chunk index
+// rather than as they finish
+
+// weighted average of the partial distributions, like chunks of a simulation weighted by their
+// number of simulations; each bucket is reduced separately as a fixed pairwise tree over partials
+pub fn merge_partial_probs(partials: &[(HashMap<i32, f64>, f64)]) -> HashMap<i32, f64> {
+    let total_weight = pairwise_sum(&partials.iter().map(|(_, w)| *w).collect::<Vec<_>>());
+    if total_weight <= 0.0 {
+        return HashMap::new();
+    }
+    // sorted keys, so the same buckets are visited in the same order every run
+    let values: BTreeSet<i32> = partials
+        .iter()
+        .flat_map(|(probs, _)| probs.keys().copied())
+        .collect();
+    values
+        .into_iter()
+        .map(|val| {
+            let weighted_probs: Vec<f64> = partials
+                .iter()
+                .map(|(probs, weight)| probs.get(&val).copied().unwrap_or(0.0) * weight)
+                .collect();
+            (val, pairwise_sum(&weighted_probs) / total_weight)
+        })
+        .collect()
+}
+
+// halves are summed recursively, and leaves with Neumaier compensation, so rounding error
+// stays tiny and the result is the same for the same input order however the work was split
+pub fn pairwise_sum(vals: &[f64]) -> f64 {
+    const LEAF_SIZE: usize = 8;
+    if vals.len() <= LEAF_SIZE {
+        return compensated_sum(vals);
+    }
+    let (left, right) = vals.split_at(vals.len() / 2);
+    compensated_sum(&[pairwise_sum(left), pairwise_sum(right)])
+}
+
+pub fn compensated_sum(vals: &[f64]) -> f64 {
+    let mut sum = 0.0;
+    let mut compensation = 0.0;
+    for val in vals.iter() {
+        let next_sum = sum + val;
+        compensation += if sum.abs() >= val.abs() {
+            (sum - next_sum) + val
+        } else {
+            (val - next_sum) + sum
+        };
+        sum = next_sum;
+    }
+    sum + compensation
+}
@@ -0,0 +1,26 @@
+use super::calc_error::CalcError;
+
+// generous cap on stats that count dice, attacks or damage, which engines loop over and multiply
+// together; real profiles stay far below it, and past it a calc would just hang the UI
+pub const MAX_COUNT_STAT: i32 = 100;
+
+// a model or options whose stats each have an inclusive (JS property name, min, max) range, so
+// the UI can build its inputs from them and every entry point checks inputs the same way
+pub trait RangedStats {
+    const STAT_RANGES: &'static [(&'static str, i32, i32)];
+
+    // in the same order as STAT_RANGES
+    fn stat_values(&self) -> Vec<i32>;
+
+    fn validate_stats(&self) -> Result<(), CalcError> {
+        let is_valid = Self::STAT_RANGES
+            .iter()
+            .zip(self.stat_values())
+            .all(|((_, min, max), val)| (*min..=*max).contains(&val));
+        if is_valid {
+            Ok(())
+        } else {
+            Err(CalcError::InvalidStat)
+        }
+    }
+}
@@ -1,8 +1,59 @@
-#[allow(non_snake_case)]
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 use std::collections::HashMap;
 use tsify::Tsify;
-use wasm_bindgen::JsValue;
+use wasm_bindgen::prelude::*;
+
+// bumped whenever a structured result changes shape; results saved by older app versions
+// still deserialize, with anything missing filled in
+pub const RESULT_SCHEMA_VERSION: u32 = 3;
+
+// v1 results predate the version field
+pub fn result_schema_v1() -> u32 {
+    1
+}
+
+// list results were bare arrays in v1 and are now an object with the version and the items
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum VersionedItems<T> {
+    V1(Vec<T>),
+    #[serde(rename_all = "camelCase")]
+    V2 {
+        result_schema_version: u32,
+        items: Vec<T>,
+    },
+}
+
+pub fn deserialize_versioned_items<'de, D, T>(deserializer: D) -> Result<(u32, Vec<T>), D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Ok(match VersionedItems::deserialize(deserializer)? {
+        VersionedItems::V1(items) => (result_schema_v1(), items),
+        VersionedItems::V2 {
+            result_schema_version,
+            items,
+        } => (result_schema_version, items),
+    })
+}
+
+// for list results, which need their own Deserialize to also load v1's bare arrays
+#[macro_export]
+macro_rules! impl_versioned_items_deserialize {
+    ($list_type:ident) => {
+        impl<'de> serde::Deserialize<'de> for $list_type {
+            fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let (result_schema_version, items) =
+                    $crate::common::ts_types::deserialize_versioned_items(deserializer)?;
+                Ok($list_type {
+                    result_schema_version,
+                    items,
+                })
+            }
+        }
+    };
+}
 
 // unfortunately, the following shows up in the generated dice_sim.d.ts as `Record<number, number>`
 // and we need https://github.com/madonoharu/tsify/pull/31 to get merged to get Map<number, number> instead
@@ -10,6 +61,336 @@ use wasm_bindgen::JsValue;
 #[tsify(into_wasm_abi, from_wasm_abi)]
 pub struct ProbMap(pub HashMap<i32, f64>);
 
+// values in ascending order, with pmf[i] = P(X = values[i]) and cdf[i] = P(X <= values[i])
+#[derive(Tsify, Serialize, Deserialize)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+#[serde(rename_all = "camelCase")]
+pub struct PmfCdf {
+    #[serde(default = "result_schema_v1")]
+    pub result_schema_version: u32,
+    pub values: Vec<i32>,
+    pub pmf: Vec<f64>,
+    pub cdf: Vec<f64>,
+}
+
+impl PmfCdf {
+    pub fn from_probs(probs: &HashMap<i32, f64>) -> PmfCdf {
+        let mut sorted_probs: Vec<(i32, f64)> = probs.iter().map(|(k, v)| (*k, *v)).collect();
+        sorted_probs.sort_by_key(|(val, _)| *val);
+
+        let mut cumulative_prob = 0.0;
+        let mut pmf_cdf = PmfCdf {
+            result_schema_version: RESULT_SCHEMA_VERSION,
+            values: Vec::with_capacity(sorted_probs.len()),
+            pmf: Vec::with_capacity(sorted_probs.len()),
+            cdf: Vec::with_capacity(sorted_probs.len()),
+        };
+        for (val, prob) in sorted_probs {
+            cumulative_prob += prob;
+            pmf_cdf.values.push(val);
+            pmf_cdf.pmf.push(prob);
+            pmf_cdf.cdf.push(cumulative_prob);
+        }
+        pmf_cdf
+    }
+}
+
+// every value from min_value up to the max, with probs[i] = P(X = min_value + i), so values that
+// can't happen are explicit zeros instead of gaps, like bar charts need
+#[derive(Tsify, Serialize, Deserialize)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+#[serde(rename_all = "camelCase")]
+pub struct DenseProbs {
+    #[serde(default = "result_schema_v1")]
+    pub result_schema_version: u32,
+    pub min_value: i32,
+    pub probs: Vec<f64>,
+}
+
+impl DenseProbs {
+    pub fn from_probs(probs: &HashMap<i32, f64>) -> DenseProbs {
+        let min_value = probs.keys().min().copied().unwrap_or(0);
+        let mut dense_probs = match probs.keys().max() {
+            Some(max_value) => vec![0.0; (max_value - min_value + 1) as usize],
+            None => Vec::new(),
+        };
+        for (val, prob) in probs.iter() {
+            dense_probs[(val - min_value) as usize] = *prob;
+        }
+        DenseProbs {
+            result_schema_version: RESULT_SCHEMA_VERSION,
+            min_value,
+            probs: dense_probs,
+        }
+    }
+}
+
+#[derive(Tsify, Serialize, Deserialize)]
+pub struct ValueProb {
+    pub value: i32,
+    pub prob: f64,
+}
+
+// the k most probable values in descending prob order, with everything else lumped together
+#[derive(Tsify, Serialize, Deserialize)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+#[serde(rename_all = "camelCase")]
+pub struct TopKProbs {
+    #[serde(default = "result_schema_v1")]
+    pub result_schema_version: u32,
+    pub top: Vec<ValueProb>,
+    pub other_prob: f64,
+}
+
+impl TopKProbs {
+    pub fn from_probs(probs: &HashMap<i32, f64>, k: usize) -> TopKProbs {
+        let mut sorted_probs: Vec<ValueProb> = probs
+            .iter()
+            .map(|(value, prob)| ValueProb {
+                value: *value,
+                prob: *prob,
+            })
+            .collect();
+        // ties are broken by value so results are stable across HashMap orderings
+        sorted_probs.sort_by(|a, b| b.prob.total_cmp(&a.prob).then(a.value.cmp(&b.value)));
+
+        let other_prob = sorted_probs.iter().skip(k).map(|vp| vp.prob).sum();
+        sorted_probs.truncate(k);
+        TopKProbs {
+            result_schema_version: RESULT_SCHEMA_VERSION,
+            top: sorted_probs,
+            other_prob,
+        }
+    }
+}
+
+// values ascending, with getters that hand JS an Int32Array and a Float32Array;
+// half the size of f64 probs, which is plenty of precision for display
+#[wasm_bindgen]
+pub struct ProbsF32 {
+    values: Vec<i32>,
+    probs: Vec<f32>,
+}
+
+#[wasm_bindgen]
+impl ProbsF32 {
+    #[wasm_bindgen(getter)]
+    pub fn values(&self) -> Vec<i32> {
+        self.values.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn probs(&self) -> Vec<f32> {
+        self.probs.clone()
+    }
+}
+
+impl ProbsF32 {
+    pub fn from_probs(probs: &HashMap<i32, f64>) -> ProbsF32 {
+        let mut sorted_probs: Vec<(i32, f64)> = probs.iter().map(|(k, v)| (*k, *v)).collect();
+        sorted_probs.sort_by_key(|(val, _)| *val);
+        ProbsF32 {
+            values: sorted_probs.iter().map(|(val, _)| *val).collect(),
+            probs: sorted_probs.iter().map(|(_, prob)| *prob as f32).collect(),
+        }
+    }
+}
+
+#[derive(Tsify, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BucketDivergence {
+    pub value: i32,
+    pub reference_prob: f64,
+    pub candidate_prob: f64,
+    pub diff: f64, // candidate minus reference
+}
+
+// bucket-by-bucket comparison of two distributions over the union of their values
+#[derive(Tsify, Serialize, Deserialize)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+#[serde(rename_all = "camelCase")]
+pub struct DivergenceReport {
+    #[serde(default = "result_schema_v1")]
+    pub result_schema_version: u32,
+    pub buckets: Vec<BucketDivergence>,
+    pub max_abs_diff: f64,
+    pub total_variation_distance: f64,
+    pub reference_total_prob: f64,
+    pub candidate_total_prob: f64,
+}
+
+impl DivergenceReport {
+    pub fn from_probs(
+        reference_probs: &HashMap<i32, f64>,
+        candidate_probs: &HashMap<i32, f64>,
+    ) -> DivergenceReport {
+        let mut values: Vec<i32> = reference_probs
+            .keys()
+            .chain(candidate_probs.keys())
+            .copied()
+            .collect();
+        values.sort_unstable();
+        values.dedup();
+
+        let buckets: Vec<BucketDivergence> = values
+            .iter()
+            .map(|value| {
+                let reference_prob = *reference_probs.get(value).unwrap_or(&0.0);
+                let candidate_prob = *candidate_probs.get(value).unwrap_or(&0.0);
+                BucketDivergence {
+                    value: *value,
+                    reference_prob,
+                    candidate_prob,
+                    diff: candidate_prob - reference_prob,
+                }
+            })
+            .collect();
+
+        DivergenceReport {
+            result_schema_version: RESULT_SCHEMA_VERSION,
+            max_abs_diff: buckets.iter().map(|b| b.diff.abs()).fold(0.0, f64::max),
+            total_variation_distance: 0.5 * buckets.iter().map(|b| b.diff.abs()).sum::<f64>(),
+            reference_total_prob: reference_probs.values().sum(),
+            candidate_total_prob: candidate_probs.values().sum(),
+            buckets,
+        }
+    }
+}
+
+// consecutive values starting at start, so only the probs need sending
+#[derive(Tsify, Serialize, Deserialize)]
+pub struct ProbRun {
+    pub start: i32,
+    pub probs: Vec<f64>,
+}
+
+// smaller stand-in for a distribution, for batch and sweep responses; sub-epsilon buckets beyond
+// the outermost kept values are merged into a below/above marker just past them, and kept values
+// are either grouped into runs of consecutive values or sent as one run each
+#[derive(Tsify, Serialize, Deserialize)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+#[serde(rename_all = "camelCase")]
+pub struct CompressedProbs {
+    #[serde(default = "result_schema_v1")]
+    pub result_schema_version: u32,
+    pub runs: Vec<ProbRun>,
+    pub below_value: i32, // marker value for the merged low tail
+    pub below_prob: f64,
+    pub above_value: i32, // marker value for the merged high tail
+    pub above_prob: f64,
+}
+
+impl CompressedProbs {
+    pub fn from_probs(
+        probs: &HashMap<i32, f64>,
+        epsilon: f64,
+        run_length_encode: bool,
+    ) -> CompressedProbs {
+        let mut sorted_probs: Vec<(i32, f64)> = probs.iter().map(|(k, v)| (*k, *v)).collect();
+        sorted_probs.sort_by_key(|(val, _)| *val);
+        let first_kept_idx = sorted_probs.iter().position(|(_, prob)| *prob >= epsilon);
+        let last_kept_idx = sorted_probs.iter().rposition(|(_, prob)| *prob >= epsilon);
+
+        let (first_kept_idx, last_kept_idx) = match (first_kept_idx, last_kept_idx) {
+            (Some(first), Some(last)) => (first, last),
+            // nothing is above epsilon, so everything goes in the low tail
+            _ => {
+                return CompressedProbs {
+                    result_schema_version: RESULT_SCHEMA_VERSION,
+                    runs: vec![],
+                    below_value: sorted_probs.first().map_or(0, |(val, _)| *val),
+                    below_prob: sorted_probs.iter().map(|(_, prob)| prob).sum(),
+                    above_value: sorted_probs.last().map_or(0, |(val, _)| *val),
+                    above_prob: 0.0,
+                }
+            }
+        };
+
+        let mut runs = Vec::<ProbRun>::new();
+        for (val, prob) in sorted_probs[first_kept_idx..=last_kept_idx].iter() {
+            match runs.last_mut() {
+                Some(run) if run_length_encode && run.start + run.probs.len() as i32 == *val => {
+                    run.probs.push(*prob)
+                }
+                _ => runs.push(ProbRun {
+                    start: *val,
+                    probs: vec![*prob],
+                }),
+            }
+        }
+
+        CompressedProbs {
+            result_schema_version: RESULT_SCHEMA_VERSION,
+            runs,
+            below_value: sorted_probs[first_kept_idx].0 - 1,
+            below_prob: sorted_probs[..first_kept_idx]
+                .iter()
+                .map(|(_, prob)| prob)
+                .sum(),
+            above_value: sorted_probs[last_kept_idx].0 + 1,
+            above_prob: sorted_probs[last_kept_idx + 1..]
+                .iter()
+                .map(|(_, prob)| prob)
+                .sum(),
+        }
+    }
+
+    // tail markers come back as single buckets, so total prob is preserved but tail shape isn't
+    #[allow(dead_code)]
+    pub fn to_probs(&self) -> HashMap<i32, f64> {
+        let mut probs = HashMap::<i32, f64>::new();
+        for run in self.runs.iter() {
+            for (offset, prob) in run.probs.iter().enumerate() {
+                probs.insert(run.start + offset as i32, *prob);
+            }
+        }
+        if self.below_prob > 0.0 {
+            probs.insert(self.below_value, self.below_prob);
+        }
+        if self.above_prob > 0.0 {
+            probs.insert(self.above_value, self.above_prob);
+        }
+        probs
+    }
+}
+
+// inclusive bounds on one input stat, named like the stat's JS property, so the UI can build its
+// input constraints from the same limits the engine checks
+#[derive(Tsify, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatRange {
+    pub name: String,
+    pub min: i32,
+    pub max: i32, // i32::MAX means unbounded
+}
+
+#[derive(Tsify, Serialize)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+#[serde(rename_all = "camelCase")]
+pub struct StatRanges {
+    pub result_schema_version: u32,
+    pub items: Vec<StatRange>,
+}
+
+crate::impl_versioned_items_deserialize!(StatRanges);
+
+impl StatRanges {
+    // from inclusive (JS property name, min, max) for each stat
+    pub fn from_ranges(ranges: &[(&str, i32, i32)]) -> StatRanges {
+        StatRanges {
+            result_schema_version: RESULT_SCHEMA_VERSION,
+            items: ranges
+                .iter()
+                .map(|(name, min, max)| StatRange {
+                    name: name.to_string(),
+                    min: *min,
+                    max: *max,
+                })
+                .collect(),
+        }
+    }
+}
+
 pub trait ToJsMap {
     fn to_js_map(&self) -> js_sys::Map;
 }
@@ -28,3 +409,14 @@ where
         js_map
     }
 }
+
+// entries whose key or value isn't a number are skipped
+pub fn js_map_to_probs(js_map: &js_sys::Map) -> HashMap<i32, f64> {
+    let mut probs = HashMap::<i32, f64>::new();
+    js_map.for_each(&mut |val, key| {
+        if let (Some(key), Some(val)) = (key.as_f64(), val.as_f64()) {
+            probs.insert(key as i32, val);
+        }
+    });
+    probs
+}
@@ -0,0 +1,190 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
+use wasm_bindgen::prelude::*;
+
+use super::deadzone_model::DeadzoneModel;
+use super::deadzone_options::DeadzoneOptions;
+use super::simulator::{calc_single_round_dmg_probs, calc_winning_margin_probs, tidy_output_probs};
+use super::validation::validate_inputs;
+use crate::common::add_to_map_value;
+use crate::common::calc_error::CalcError;
+use crate::common::ts_types::RESULT_SCHEMA_VERSION;
+
+#[derive(Tsify, Serialize, Deserialize, Clone, Copy)]
+pub enum DeadzoneAction {
+    Shoot,
+    AimedShoot,
+    BlazeAway, // no damage, but pins the defender if the attacker wins the roll
+    Fight,     // defender fights back
+}
+
+#[derive(Tsify, Serialize, Deserialize)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct ActionSequence(pub Vec<DeadzoneAction>);
+
+#[derive(Tsify, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActivationEndState {
+    pub attacker_dmg_taken: i32,
+    pub defender_dmg_taken: i32,
+    pub defender_pinned: bool,
+    pub prob: f64,
+}
+
+#[derive(Tsify, Serialize)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+#[serde(rename_all = "camelCase")]
+pub struct ActivationEndStates {
+    pub result_schema_version: u32,
+    pub items: Vec<ActivationEndState>,
+}
+
+crate::impl_versioned_items_deserialize!(ActivationEndStates);
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+struct ActivationState {
+    attacker_dmg_taken: i32, // capped at hp
+    defender_dmg_taken: i32, // capped at hp
+    defender_pinned: bool,
+}
+
+// resolves the attacker's actions in order against one defender, carrying wounds and
+// suppression from each action to the next; once either model is dead, later actions are skipped.
+// every action is an attack, so each gets the options' fatigue penalties for the ones before it
+#[wasm_bindgen(js_name = "deadzoneCalcActionSequence")]
+pub fn deadzone_calc_action_sequence(
+    attacker: &DeadzoneModel,
+    defender: &DeadzoneModel,
+    options: &DeadzoneOptions,
+    actions: ActionSequence,
+) -> Result<ActivationEndStates, CalcError> {
+    validate_inputs(attacker, defender, options)?;
+    let start_state = ActivationState {
+        attacker_dmg_taken: 0,
+        defender_dmg_taken: 0,
+        defender_pinned: defender.pinned,
+    };
+    let mut state_probs = HashMap::from([(start_state, 1.0)]);
+
+    for (num_prior_attacks, action) in actions.0.iter().enumerate() {
+        let action_attacker = fatigued_attacker(attacker, options, num_prior_attacks as i32);
+        let mut next_state_probs = HashMap::<ActivationState, f64>::new();
+        // outcomes only depend on whether the defender is pinned, so calc each at most once per action
+        let mut outcome_probs_by_pinned = HashMap::<bool, HashMap<i32, f64>>::new();
+        for (state, state_prob) in state_probs.iter() {
+            if state.attacker_dmg_taken >= attacker.hp || state.defender_dmg_taken >= defender.hp {
+                add_to_map_value(&mut next_state_probs, state, *state_prob);
+                continue;
+            }
+            let outcome_probs = outcome_probs_by_pinned
+                .entry(state.defender_pinned)
+                .or_insert_with(|| {
+                    calc_action_outcome_probs(
+                        &action_attacker,
+                        defender,
+                        options,
+                        *action,
+                        state.defender_pinned,
+                    )
+                });
+            for (outcome, outcome_prob) in outcome_probs.iter() {
+                let next_state = apply_action_outcome(state, *action, *outcome, attacker, defender);
+                add_to_map_value(
+                    &mut next_state_probs,
+                    &next_state,
+                    state_prob * outcome_prob,
+                );
+            }
+        }
+        state_probs = next_state_probs;
+    }
+
+    Ok(ActivationEndStates {
+        result_schema_version: RESULT_SCHEMA_VERSION,
+        items: tidy_output_probs(&state_probs, options)
+            .iter()
+            .map(|(state, prob)| ActivationEndState {
+                attacker_dmg_taken: state.attacker_dmg_taken,
+                defender_dmg_taken: state.defender_dmg_taken,
+                defender_pinned: state.defender_pinned,
+                prob: *prob,
+            })
+            .collect(),
+    })
+}
+
+// the penalties stack, so the third attack gets twice the second's; dice can't go below none, and
+// the stat can't go past 9+, which never succeeds without house rules
+fn fatigued_attacker(
+    attacker: &DeadzoneModel,
+    options: &DeadzoneOptions,
+    num_prior_attacks: i32,
+) -> DeadzoneModel {
+    let mut fatigued_attacker = *attacker;
+    fatigued_attacker.num_dice = std::cmp::max(
+        0,
+        attacker.num_dice
+            - options
+                .fatigue_dice_penalty
+                .saturating_mul(num_prior_attacks),
+    );
+    fatigued_attacker.dice_stat = std::cmp::min(
+        9,
+        attacker.dice_stat
+            + options
+                .fatigue_pip_penalty
+                .saturating_mul(num_prior_attacks),
+    );
+    fatigued_attacker
+}
+
+// for Blaze Away, the outcome is the winning margin, since only a win pins; otherwise it's signed dmg like deadzoneCalcDmgProbs
+fn calc_action_outcome_probs(
+    attacker: &DeadzoneModel,
+    defender: &DeadzoneModel,
+    options: &DeadzoneOptions,
+    action: DeadzoneAction,
+    defender_pinned: bool,
+) -> HashMap<i32, f64> {
+    let mut action_options = *options;
+    action_options.num_rounds = 1;
+    action_options.aimed = matches!(action, DeadzoneAction::AimedShoot);
+    action_options.attacker_can_be_damaged = matches!(action, DeadzoneAction::Fight);
+    let mut action_defender = *defender;
+    action_defender.pinned = defender_pinned;
+
+    match action {
+        DeadzoneAction::BlazeAway => {
+            calc_winning_margin_probs(attacker, &action_defender, &action_options)
+        }
+        _ => calc_single_round_dmg_probs(attacker, &action_defender, &action_options),
+    }
+}
+
+fn apply_action_outcome(
+    state: &ActivationState,
+    action: DeadzoneAction,
+    outcome: i32,
+    attacker: &DeadzoneModel,
+    defender: &DeadzoneModel,
+) -> ActivationState {
+    let mut next_state = *state;
+    match action {
+        DeadzoneAction::BlazeAway => {
+            next_state.defender_pinned |= outcome > 0;
+        }
+        _ => {
+            next_state.defender_dmg_taken = std::cmp::min(
+                defender.hp,
+                state.defender_dmg_taken + std::cmp::max(0, outcome),
+            );
+            next_state.attacker_dmg_taken = std::cmp::min(
+                attacker.hp,
+                state.attacker_dmg_taken + std::cmp::max(0, -outcome),
+            );
+        }
+    }
+    next_state
+}
@@ -0,0 +1,234 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
+use wasm_bindgen::prelude::*;
+
+use super::deadzone_model::{DeadzoneModel, DeadzoneModelList};
+use super::deadzone_options::DeadzoneOptions;
+use super::roll_params::aim_bonus_dice;
+use super::simulator::{calc_dmg_probs, tidy_output_probs};
+use super::validation::validate_inputs;
+use crate::common::calc_error::CalcError;
+use crate::common::ts_types::{result_schema_v1, ProbMap, RESULT_SCHEMA_VERSION};
+use crate::common::{add_to_map_value, prob_at_least};
+
+// the search tries every split, so past this many it would hang the UI for a long time
+const MAX_ALLOCATIONS: u64 = 1_000_000;
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum AllocationObjective {
+    ExpectedKills,
+    ExpectedDmg, // each defender's dmg is capped at its hp, since overkill is wasted
+}
+
+#[derive(Tsify, Serialize, Deserialize)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+#[serde(rename_all = "camelCase")]
+pub struct DiceAllocation {
+    #[serde(default = "result_schema_v1")]
+    pub result_schema_version: u32,
+    pub dice_per_defender: Vec<i32>,
+    pub objective_value: f64,
+    pub num_kills_probs: ProbMap,
+    pub dmg_probs_per_defender: Vec<ProbMap>,
+}
+
+// tries every way of splitting the attacker's numDice among the defenders, where each share is
+// resolved as its own attack with the same options; the aimed die is one more die to split,
+// rather than a bonus die on every share
+#[wasm_bindgen(js_name = "deadzoneOptimizeDiceAllocation")]
+pub fn deadzone_optimize_dice_allocation(
+    attacker: &DeadzoneModel,
+    defenders: &DeadzoneModelList,
+    options: &DeadzoneOptions,
+    objective: AllocationObjective,
+) -> Result<DiceAllocation, CalcError> {
+    let defenders = defenders.models();
+    for defender in defenders.iter() {
+        validate_inputs(attacker, defender, options)?;
+    }
+    let max_dice = std::cmp::max(0, attacker.num_dice) + aim_bonus_dice(attacker, true, options);
+    if num_allocations(max_dice, defenders.len()) > MAX_ALLOCATIONS {
+        return Err(CalcError::BudgetExceeded);
+    }
+    let mut share_options = *options;
+    share_options.aimed = false;
+
+    // dmg_probs_by_num_dice[defender_idx][num_dice]
+    let dmg_probs_by_num_dice: Vec<Vec<HashMap<i32, f64>>> = defenders
+        .iter()
+        .map(|defender| {
+            (0..=max_dice)
+                .map(|num_dice| {
+                    let mut partial_attacker = *attacker;
+                    partial_attacker.num_dice = num_dice;
+                    calc_dmg_probs(&partial_attacker, defender, &share_options)
+                })
+                .collect()
+        })
+        .collect();
+    let objective_values: Vec<Vec<f64>> = defenders
+        .iter()
+        .zip(dmg_probs_by_num_dice.iter())
+        .map(|(defender, dmg_probs_list)| {
+            dmg_probs_list
+                .iter()
+                .map(|dmg_probs| calc_objective_value(dmg_probs, defender, objective))
+                .collect()
+        })
+        .collect();
+
+    let mut best_allocation = vec![0; defenders.len()];
+    let mut best_value = f64::NEG_INFINITY;
+    let mut allocation = vec![0; defenders.len()];
+    search_allocations(
+        &objective_values,
+        max_dice,
+        0,
+        &mut allocation,
+        &mut best_allocation,
+        &mut best_value,
+    );
+
+    let mut num_kills_probs = HashMap::from([(0, 1.0)]);
+    for (defender_idx, defender) in defenders.iter().enumerate() {
+        let dmg_probs =
+            &dmg_probs_by_num_dice[defender_idx][best_allocation[defender_idx] as usize];
+        let kill_prob = prob_at_least(dmg_probs, defender.hp);
+        let mut next_num_kills_probs = HashMap::<i32, f64>::new();
+        for (num_kills, prob) in num_kills_probs.iter() {
+            add_to_map_value(
+                &mut next_num_kills_probs,
+                &(num_kills + 1),
+                prob * kill_prob,
+            );
+            add_to_map_value(
+                &mut next_num_kills_probs,
+                num_kills,
+                prob * (1.0 - kill_prob),
+            );
+        }
+        num_kills_probs = next_num_kills_probs;
+    }
+
+    Ok(DiceAllocation {
+        result_schema_version: RESULT_SCHEMA_VERSION,
+        dmg_probs_per_defender: best_allocation
+            .iter()
+            .enumerate()
+            .map(|(defender_idx, num_dice)| {
+                ProbMap(tidy_output_probs(
+                    &dmg_probs_by_num_dice[defender_idx][*num_dice as usize],
+                    options,
+                ))
+            })
+            .collect(),
+        dice_per_defender: best_allocation,
+        objective_value: if defenders.is_empty() {
+            0.0
+        } else {
+            best_value
+        },
+        num_kills_probs: ProbMap(tidy_output_probs(&num_kills_probs, options)),
+    })
+}
+
+fn calc_objective_value(
+    dmg_probs: &HashMap<i32, f64>,
+    defender: &DeadzoneModel,
+    objective: AllocationObjective,
+) -> f64 {
+    match objective {
+        AllocationObjective::ExpectedKills => prob_at_least(dmg_probs, defender.hp),
+        AllocationObjective::ExpectedDmg => dmg_probs
+            .iter()
+            .map(|(dmg, prob)| (*dmg).clamp(0, defender.hp) as f64 * prob)
+            .sum(),
+    }
+}
+
+// compositions of num_dice into num_defenders shares, C(num_dice + num_defenders - 1, num_defenders - 1);
+// stops counting once past MAX_ALLOCATIONS
+fn num_allocations(num_dice: i32, num_defenders: usize) -> u64 {
+    if num_defenders == 0 {
+        return 0;
+    }
+    let num_dice = num_dice as u64;
+    let mut count: u64 = 1;
+    for i in 1..num_defenders as u64 {
+        count = count * (num_dice + i) / i;
+        if count > MAX_ALLOCATIONS {
+            break;
+        }
+    }
+    count
+}
+
+// depth-first over all compositions of the remaining dice; the last defender gets whatever is left
+fn search_allocations(
+    objective_values: &[Vec<f64>],
+    remaining_dice: i32,
+    defender_idx: usize,
+    allocation: &mut Vec<i32>,
+    best_allocation: &mut Vec<i32>,
+    best_value: &mut f64,
+) {
+    if defender_idx >= objective_values.len() {
+        return;
+    }
+    let is_last_defender = defender_idx + 1 == objective_values.len();
+    let min_dice = if is_last_defender { remaining_dice } else { 0 };
+
+    for num_dice in min_dice..=remaining_dice {
+        allocation[defender_idx] = num_dice;
+        if is_last_defender {
+            let value: f64 = allocation
+                .iter()
+                .enumerate()
+                .map(|(idx, dice)| objective_values[idx][*dice as usize])
+                .sum();
+            if value > *best_value {
+                *best_value = value;
+                best_allocation.clone_from(allocation);
+            }
+        } else {
+            search_allocations(
+                objective_values,
+                remaining_dice - num_dice,
+                defender_idx + 1,
+                allocation,
+                best_allocation,
+                best_value,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn too_many_allocations_are_rejected() {
+        // 4 dice among 3 defenders
+        assert_eq!(num_allocations(4, 3), 15);
+        assert_eq!(num_allocations(4, 1), 1);
+
+        // 20 dice among 10 defenders is C(29, 9), about 10 million splits
+        let mut attacker = DeadzoneModel::new();
+        attacker.num_dice = 20;
+        let mut defenders = DeadzoneModelList::new();
+        for _ in 0..10 {
+            defenders.push(&DeadzoneModel::new());
+        }
+        let result = deadzone_optimize_dice_allocation(
+            &attacker,
+            &defenders,
+            &DeadzoneOptions::new(),
+            AllocationObjective::ExpectedKills,
+        );
+        assert_eq!(result.err(), Some(CalcError::BudgetExceeded));
+    }
+}
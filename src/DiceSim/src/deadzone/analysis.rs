@@ -0,0 +1,267 @@
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
+use wasm_bindgen::prelude::*;
+
+use super::deadzone_model::{DeadzoneModel, DeadzoneStat};
+use super::deadzone_options::DeadzoneOptions;
+use super::simulator::{calc_dmg_probs, calc_dmg_probs_by_round, calc_success_probs};
+use super::validation::validate_inputs;
+use crate::common::calc_error::CalcError;
+use crate::common::normalizer::NormalizedMetrics;
+use crate::common::ts_types::{js_map_to_probs, DivergenceReport, RESULT_SCHEMA_VERSION};
+use crate::common::{expected_overkill, expected_value, prob_at_least, std_dev};
+
+// plain numbers instead of a map or serialized object, so it's cheap enough to call on every
+// slider movement
+#[wasm_bindgen]
+#[derive(Clone, Copy)]
+pub struct DmgStats {
+    #[wasm_bindgen(js_name = killProb)]
+    pub kill_prob: f64,
+    #[wasm_bindgen(js_name = expectedDmg)]
+    pub expected_dmg: f64,
+    #[wasm_bindgen(js_name = stdDev)]
+    pub std_dev: f64,
+}
+
+#[wasm_bindgen(js_name = "deadzoneCalcDmgStats")]
+pub fn deadzone_calc_dmg_stats(
+    attacker: &DeadzoneModel,
+    defender: &DeadzoneModel,
+    options: &DeadzoneOptions,
+) -> Result<DmgStats, CalcError> {
+    validate_inputs(attacker, defender, options)?;
+    let dmg_probs = calc_dmg_probs(attacker, defender, options);
+    Ok(DmgStats {
+        kill_prob: prob_at_least(&dmg_probs, defender.hp),
+        expected_dmg: expected_value(&dmg_probs),
+        std_dev: std_dev(&dmg_probs),
+    })
+}
+
+// one model's roll on its own, before anything the opponent does, for side-by-side model cards;
+// counts explosions, rerolls and kept dice like a real roll does
+#[wasm_bindgen]
+#[derive(Clone, Copy)]
+pub struct SuccessStats {
+    #[wasm_bindgen(js_name = expectedSuccesses)]
+    pub expected_successes: f64,
+    pub variance: f64,
+    #[wasm_bindgen(js_name = zeroSuccessesProb)]
+    pub zero_successes_prob: f64,
+}
+
+#[wasm_bindgen(js_name = "deadzoneCalcSuccessStats")]
+pub fn deadzone_calc_success_stats(
+    model: &DeadzoneModel,
+    is_attacker: bool,
+    options: &DeadzoneOptions,
+) -> Result<SuccessStats, CalcError> {
+    // the model stands in for its own opponent, just so both sides get validated
+    validate_inputs(model, model, options)?;
+    let success_probs = calc_success_probs(model, is_attacker, options);
+    Ok(SuccessStats {
+        expected_successes: expected_value(&success_probs),
+        variance: std_dev(&success_probs).powi(2),
+        zero_successes_prob: success_probs.get(&0).copied().unwrap_or(0.0),
+    })
+}
+
+#[derive(Tsify, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RoundDmgStats {
+    pub round: i32,
+    pub expected_dmg: f64, // cumulative, and negative damage (to attacker) reduces it
+    pub kill_prob: f64,    // cumulative damage reached defender's hp
+}
+
+#[derive(Tsify, Serialize)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+#[serde(rename_all = "camelCase")]
+pub struct DmgByRound {
+    pub result_schema_version: u32,
+    pub items: Vec<RoundDmgStats>,
+}
+
+crate::impl_versioned_items_deserialize!(DmgByRound);
+
+#[wasm_bindgen(js_name = "deadzoneCalcDmgByRound")]
+pub fn deadzone_calc_dmg_by_round(
+    attacker: &DeadzoneModel,
+    defender: &DeadzoneModel,
+    options: &DeadzoneOptions,
+) -> Result<DmgByRound, CalcError> {
+    validate_inputs(attacker, defender, options)?;
+    let dmg_probs_by_round = calc_dmg_probs_by_round(attacker, defender, options);
+
+    Ok(DmgByRound {
+        result_schema_version: RESULT_SCHEMA_VERSION,
+        items: dmg_probs_by_round
+            .iter()
+            .enumerate()
+            .map(|(round_idx, dmg_probs)| RoundDmgStats {
+                round: round_idx as i32 + 1,
+                expected_dmg: expected_value(dmg_probs),
+                kill_prob: prob_at_least(dmg_probs, defender.hp),
+            })
+            .collect(),
+    })
+}
+
+// wasted damage dealt to the defender past its hp, over all of the options' rounds
+#[wasm_bindgen(js_name = "deadzoneCalcExpectedOverkill")]
+pub fn deadzone_calc_expected_overkill(
+    attacker: &DeadzoneModel,
+    defender: &DeadzoneModel,
+    options: &DeadzoneOptions,
+) -> Result<f64, CalcError> {
+    validate_inputs(attacker, defender, options)?;
+    Ok(expected_overkill(
+        &calc_dmg_probs(attacker, defender, options),
+        defender.hp,
+    ))
+}
+
+// the defender is the reference target, so its hp are the wounds the metrics are relative to
+#[wasm_bindgen(js_name = "deadzoneCalcNormalizedMetrics")]
+pub fn deadzone_calc_normalized_metrics(
+    attacker: &DeadzoneModel,
+    defender: &DeadzoneModel,
+    options: &DeadzoneOptions,
+) -> Result<NormalizedMetrics, CalcError> {
+    validate_inputs(attacker, defender, options)?;
+    NormalizedMetrics::from_dmg_probs(&calc_dmg_probs(attacker, defender, options), defender.hp)
+}
+
+// element i is the kill prob if the attack is repeated for i+1 rounds, up to the options' numRounds;
+// these are the by-round stats' kill probs, so this costs the same as one call
+#[wasm_bindgen(js_name = "deadzoneCalcKillProbByNumRounds")]
+pub fn deadzone_calc_kill_prob_by_num_rounds(
+    attacker: &DeadzoneModel,
+    defender: &DeadzoneModel,
+    options: &DeadzoneOptions,
+) -> Result<Vec<f64>, CalcError> {
+    Ok(deadzone_calc_dmg_by_round(attacker, defender, options)?
+        .items
+        .iter()
+        .map(|round_stats| round_stats.kill_prob)
+        .collect())
+}
+
+#[wasm_bindgen(js_name = "deadzoneCalcKillProbByNumRoundsF32")]
+pub fn deadzone_calc_kill_prob_by_num_rounds_f32(
+    attacker: &DeadzoneModel,
+    defender: &DeadzoneModel,
+    options: &DeadzoneOptions,
+) -> Result<Vec<f32>, CalcError> {
+    Ok(
+        deadzone_calc_kill_prob_by_num_rounds(attacker, defender, options)?
+            .iter()
+            .map(|prob| *prob as f32)
+            .collect(),
+    )
+}
+
+// compares the dmg probs that the legacy TypeScript engine got for these inputs (the reference)
+// against this crate's result (the candidate)
+#[wasm_bindgen(js_name = "deadzoneCompareDmgProbs")]
+pub fn deadzone_compare_dmg_probs(
+    attacker: &DeadzoneModel,
+    defender: &DeadzoneModel,
+    options: &DeadzoneOptions,
+    js_engine_dmg_probs: &js_sys::Map,
+) -> Result<DivergenceReport, CalcError> {
+    validate_inputs(attacker, defender, options)?;
+    Ok(DivergenceReport::from_probs(
+        &js_map_to_probs(js_engine_dmg_probs),
+        &calc_dmg_probs(attacker, defender, options),
+    ))
+}
+
+#[derive(Tsify, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DieCountValue {
+    pub num_dice: i32,
+    pub expected_dmg: f64,
+    pub kill_prob: f64,
+    pub marginal_expected_dmg: f64, // gained by adding this die
+    pub marginal_kill_prob: f64,
+}
+
+#[derive(Tsify, Serialize)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+#[serde(rename_all = "camelCase")]
+pub struct MarginalDieValues {
+    pub result_schema_version: u32,
+    pub items: Vec<DieCountValue>,
+}
+
+crate::impl_versioned_items_deserialize!(MarginalDieValues);
+
+// how much each additional attacker die is worth, from 1 up to max_num_dice
+#[wasm_bindgen(js_name = "deadzoneCalcMarginalDieValues")]
+pub fn deadzone_calc_marginal_die_values(
+    attacker: &DeadzoneModel,
+    defender: &DeadzoneModel,
+    options: &DeadzoneOptions,
+    max_num_dice: i32,
+) -> Result<MarginalDieValues, CalcError> {
+    validate_inputs(attacker, defender, options)?;
+    let (min, max) = DeadzoneModel::stat_range(DeadzoneStat::NumDice);
+    if !(min..=max).contains(&max_num_dice) {
+        return Err(CalcError::InvalidStat);
+    }
+    // the budgets only grow with the dice, so the biggest pool is the one to check
+    let mut varied_attacker = *attacker;
+    varied_attacker.num_dice = max_num_dice;
+    validate_inputs(&varied_attacker, defender, options)?;
+
+    let mut die_values = Vec::<DieCountValue>::new();
+    varied_attacker.num_dice = 0;
+    let no_dice_dmg_probs = calc_dmg_probs(&varied_attacker, defender, options);
+    let mut prev_expected_dmg = expected_value(&no_dice_dmg_probs);
+    let mut prev_kill_prob = prob_at_least(&no_dice_dmg_probs, defender.hp);
+
+    for num_dice in 1..=max_num_dice {
+        varied_attacker.num_dice = num_dice;
+        let dmg_probs = calc_dmg_probs(&varied_attacker, defender, options);
+        let expected_dmg = expected_value(&dmg_probs);
+        let kill_prob = prob_at_least(&dmg_probs, defender.hp);
+        die_values.push(DieCountValue {
+            num_dice,
+            expected_dmg,
+            kill_prob,
+            marginal_expected_dmg: expected_dmg - prev_expected_dmg,
+            marginal_kill_prob: kill_prob - prev_kill_prob,
+        });
+        prev_expected_dmg = expected_dmg;
+        prev_kill_prob = kill_prob;
+    }
+    Ok(MarginalDieValues {
+        result_schema_version: RESULT_SCHEMA_VERSION,
+        items: die_values,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn marginal_die_values_validate_max_num_dice() {
+        let model = DeadzoneModel::new();
+        let mut options = DeadzoneOptions::new();
+        options.num_simulations = 0;
+        let die_values = deadzone_calc_marginal_die_values(&model, &model, &options, 3).unwrap();
+        assert_eq!(die_values.items.len(), 3);
+        assert_eq!(
+            deadzone_calc_marginal_die_values(&model, &model, &options, -1).err(),
+            Some(CalcError::InvalidStat)
+        );
+        // past what the exact engine can enumerate
+        assert_eq!(
+            deadzone_calc_marginal_die_values(&model, &model, &options, 1_000).err(),
+            Some(CalcError::BudgetExceeded)
+        );
+    }
+}
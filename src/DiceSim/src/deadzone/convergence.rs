@@ -0,0 +1,103 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
+use wasm_bindgen::prelude::*;
+
+use super::deadzone_model::DeadzoneModel;
+use super::deadzone_options::DeadzoneOptions;
+use super::roll_params::{RollParams, PIP_HI, PIP_LO};
+use super::simulator::{
+    calc_multi_round_dmg_probs, combine_opposed_successes, roll_success_counts,
+    success_counts_to_probs,
+};
+use super::validation::validate_inputs;
+use crate::common::calc_error::CalcError;
+use crate::common::ts_types::RESULT_SCHEMA_VERSION;
+use crate::common::{add_to_map_value, expected_value, prob_at_least};
+
+#[derive(Tsify, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConvergencePoint {
+    pub num_simulations: i32, // simulations so far, for each of attacker and defender
+    pub expected_dmg: f64,
+    pub kill_prob: f64,
+}
+
+#[derive(Tsify, Serialize)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+#[serde(rename_all = "camelCase")]
+pub struct ConvergenceSeries {
+    pub result_schema_version: u32,
+    pub items: Vec<ConvergencePoint>,
+}
+
+crate::impl_versioned_items_deserialize!(ConvergenceSeries);
+
+// estimates after every checkpointInterval simulations, up to the options' numSimulations;
+// if the later estimates have stopped wandering, numSimulations is plenty.
+// rolls fresh instead of using the cache, so each checkpoint only sees the samples so far
+#[wasm_bindgen(js_name = "deadzoneCalcConvergence")]
+pub fn deadzone_calc_convergence(
+    attacker: &DeadzoneModel,
+    defender: &DeadzoneModel,
+    options: &DeadzoneOptions,
+    checkpoint_interval: i32,
+) -> Result<ConvergenceSeries, CalcError> {
+    validate_inputs(attacker, defender, options)?;
+    if checkpoint_interval <= 0 {
+        return Err(CalcError::InvalidStat);
+    }
+    if options.num_simulations == 0 {
+        // the exact engine has nothing to converge
+        return Err(CalcError::Unsupported);
+    }
+
+    let mut rng = rand::thread_rng();
+    let die_distribution = rand::distributions::Uniform::new(PIP_LO, PIP_HI + 1);
+    let mut atk_roll_params = RollParams::new(attacker, true, options);
+    let mut def_roll_params = RollParams::new(defender, false, options);
+    let mut atk_success_counts = HashMap::<i32, i32>::new();
+    let mut def_success_counts = HashMap::<i32, i32>::new();
+    let mut num_simulations_so_far = 0;
+    let mut points = vec![];
+
+    while num_simulations_so_far < options.num_simulations {
+        let num_new_simulations = std::cmp::min(
+            checkpoint_interval,
+            options.num_simulations - num_simulations_so_far,
+        );
+        atk_roll_params.num_simulations = num_new_simulations;
+        def_roll_params.num_simulations = num_new_simulations;
+        for (success_counts, roll_params) in [
+            (&mut atk_success_counts, &atk_roll_params),
+            (&mut def_success_counts, &def_roll_params),
+        ] {
+            for (num_successes, count) in
+                roll_success_counts(&die_distribution, &mut rng, roll_params).iter()
+            {
+                add_to_map_value(success_counts, num_successes, *count);
+            }
+        }
+        num_simulations_so_far += num_new_simulations;
+
+        let mut dmg_probs = combine_opposed_successes(
+            attacker,
+            defender,
+            options,
+            &success_counts_to_probs(&atk_success_counts, num_simulations_so_far),
+            &success_counts_to_probs(&def_success_counts, num_simulations_so_far),
+        );
+        dmg_probs = calc_multi_round_dmg_probs(&dmg_probs, attacker, defender, options);
+        points.push(ConvergencePoint {
+            num_simulations: num_simulations_so_far,
+            expected_dmg: expected_value(&dmg_probs),
+            kill_prob: prob_at_least(&dmg_probs, defender.hp),
+        });
+    }
+
+    Ok(ConvergenceSeries {
+        result_schema_version: RESULT_SCHEMA_VERSION,
+        items: points,
+    })
+}
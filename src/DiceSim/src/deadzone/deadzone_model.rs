@@ -1,6 +1,15 @@
+use serde::{Deserialize, Serialize};
 use wasm_bindgen::prelude::*;
 
+use crate::common::calc_error::CalcError;
+
+pub const NUM_DIE_FACES: usize = 8;
+// most successes a single face can score, well past any published rule
+pub const FACE_SUCCESS_VALUE_RANGE: (i32, i32) = (0, 10);
+
 #[wasm_bindgen]
+#[derive(Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
 pub struct DeadzoneModel {
     pub hp: i32,
     #[wasm_bindgen(js_name = numDice)]
@@ -9,12 +18,38 @@ pub struct DeadzoneModel {
     pub dice_stat: i32,
     #[wasm_bindgen(js_name = numRerolls)]
     pub num_rerolls: i32,
+    // only this many of the best dice count, so numDice - 1 drops the lowest; rerolled dice are part of the pool
+    #[wasm_bindgen(js_name = numDiceKept)]
+    pub num_dice_kept: i32,
+    // at most this many successes count, after rerolls and keeping the best dice
+    #[wasm_bindgen(js_name = maxSuccesses)]
+    pub max_successes: i32,
     pub ap: i32,
+    // extra AP for each success the opposed roll is won by beyond the first
+    #[wasm_bindgen(js_name = apPerExcessSuccess)]
+    pub ap_per_excess_success: i32,
     pub armor: i32,
     #[wasm_bindgen(js_name = numShieldDice)]
     pub num_shield_dice: i32,
+    #[wasm_bindgen(js_name = numShieldRerolls)]
+    pub num_shield_rerolls: i32,
+    #[wasm_bindgen(js_name = shieldPipModifier)]
+    pub shield_pip_modifier: i32, // +1 means shield dice succeed on 5+ instead of 6+
     #[wasm_bindgen(js_name = toxicDmg)]
     pub toxic_dmg: i32, // additional dmg if any dmg goes through; "Dismantle" is basically toxic 1 against vehicles
+    // net successes needed per point of dmg, rounded up, so 2 means margins 1-2 do 1 dmg and 3-4 do 2
+    #[wasm_bindgen(js_name = successesPerDmg)]
+    pub successes_per_dmg: i32,
+    // winning the opposed roll by at least this many successes kills outright, whatever the wounds
+    #[wasm_bindgen(js_name = instantKillMargin)]
+    pub instant_kill_margin: i32,
+    pub pinned: bool, // suppressed, like after being hit by a Blaze Away, so rolls 1 fewer die
+    // cap on the dmg this model deals per attack, after armor, shields and toxic
+    #[wasm_bindgen(js_name = maxDmg)]
+    pub max_dmg: i32,
+    // successes scored by each face, 1 through 8, overriding diceStat; a face scoring 0 is a failure
+    #[wasm_bindgen(skip)]
+    pub face_success_values: Option<[i32; NUM_DIE_FACES]>,
 }
 
 #[wasm_bindgen]
@@ -26,10 +61,216 @@ impl DeadzoneModel {
             num_dice: 3,
             dice_stat: 5,
             num_rerolls: 0,
+            num_dice_kept: i32::MAX,
+            max_successes: i32::MAX,
             ap: 0,
+            ap_per_excess_success: 0,
             armor: 0,
             num_shield_dice: 0,
+            num_shield_rerolls: 0,
+            shield_pip_modifier: 0,
             toxic_dmg: 0,
+            successes_per_dmg: 1,
+            max_dmg: i32::MAX,
+            instant_kill_margin: i32::MAX,
+            pinned: false,
+            face_success_values: None,
+        }
+    }
+
+    // like [0, 0, 0, 0, 0, 0, 1, 2] for 7s scoring one success and 8s scoring two
+    #[wasm_bindgen(js_name = setFaceSuccessValues)]
+    pub fn set_face_success_values(&mut self, values: Vec<i32>) -> Result<(), CalcError> {
+        let values: [i32; NUM_DIE_FACES] = values.try_into().map_err(|_| CalcError::InvalidStat)?;
+        if !Self::face_success_values_in_range(&values) {
+            return Err(CalcError::InvalidStat);
+        }
+        self.face_success_values = Some(values);
+        Ok(())
+    }
+
+    // goes back to diceStat deciding successes
+    #[wasm_bindgen(js_name = clearFaceSuccessValues)]
+    pub fn clear_face_success_values(&mut self) {
+        self.face_success_values = None;
+    }
+
+    #[wasm_bindgen(getter, js_name = faceSuccessValues)]
+    pub fn face_success_values(&self) -> Option<Vec<i32>> {
+        self.face_success_values.map(|values| values.to_vec())
+    }
+}
+
+// for picking one of a model's stats at runtime, like which stat a prior or sweep is over
+#[wasm_bindgen]
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DeadzoneStat {
+    Hp,
+    NumDice,
+    DiceStat,
+    NumRerolls,
+    NumDiceKept,
+    MaxSuccesses,
+    Ap,
+    ApPerExcessSuccess,
+    Armor,
+    NumShieldDice,
+    NumShieldRerolls,
+    ShieldPipModifier,
+    ToxicDmg,
+    SuccessesPerDmg,
+    MaxDmg,
+    InstantKillMargin,
+}
+
+impl DeadzoneStat {
+    pub const ALL: [DeadzoneStat; 16] = [
+        DeadzoneStat::Hp,
+        DeadzoneStat::NumDice,
+        DeadzoneStat::DiceStat,
+        DeadzoneStat::NumRerolls,
+        DeadzoneStat::NumDiceKept,
+        DeadzoneStat::MaxSuccesses,
+        DeadzoneStat::Ap,
+        DeadzoneStat::ApPerExcessSuccess,
+        DeadzoneStat::Armor,
+        DeadzoneStat::NumShieldDice,
+        DeadzoneStat::NumShieldRerolls,
+        DeadzoneStat::ShieldPipModifier,
+        DeadzoneStat::ToxicDmg,
+        DeadzoneStat::SuccessesPerDmg,
+        DeadzoneStat::MaxDmg,
+        DeadzoneStat::InstantKillMargin,
+    ];
+}
+
+impl DeadzoneModel {
+    pub fn get_stat(&self, stat: DeadzoneStat) -> i32 {
+        match stat {
+            DeadzoneStat::Hp => self.hp,
+            DeadzoneStat::NumDice => self.num_dice,
+            DeadzoneStat::DiceStat => self.dice_stat,
+            DeadzoneStat::NumRerolls => self.num_rerolls,
+            DeadzoneStat::NumDiceKept => self.num_dice_kept,
+            DeadzoneStat::MaxSuccesses => self.max_successes,
+            DeadzoneStat::Ap => self.ap,
+            DeadzoneStat::ApPerExcessSuccess => self.ap_per_excess_success,
+            DeadzoneStat::Armor => self.armor,
+            DeadzoneStat::NumShieldDice => self.num_shield_dice,
+            DeadzoneStat::NumShieldRerolls => self.num_shield_rerolls,
+            DeadzoneStat::ShieldPipModifier => self.shield_pip_modifier,
+            DeadzoneStat::ToxicDmg => self.toxic_dmg,
+            DeadzoneStat::SuccessesPerDmg => self.successes_per_dmg,
+            DeadzoneStat::MaxDmg => self.max_dmg,
+            DeadzoneStat::InstantKillMargin => self.instant_kill_margin,
+        }
+    }
+
+    // the stat's JS property name
+    pub fn stat_js_name(stat: DeadzoneStat) -> &'static str {
+        match stat {
+            DeadzoneStat::Hp => "hp",
+            DeadzoneStat::NumDice => "numDice",
+            DeadzoneStat::DiceStat => "diceStat",
+            DeadzoneStat::NumRerolls => "numRerolls",
+            DeadzoneStat::NumDiceKept => "numDiceKept",
+            DeadzoneStat::MaxSuccesses => "maxSuccesses",
+            DeadzoneStat::Ap => "ap",
+            DeadzoneStat::ApPerExcessSuccess => "apPerExcessSuccess",
+            DeadzoneStat::Armor => "armor",
+            DeadzoneStat::NumShieldDice => "numShieldDice",
+            DeadzoneStat::NumShieldRerolls => "numShieldRerolls",
+            DeadzoneStat::ShieldPipModifier => "shieldPipModifier",
+            DeadzoneStat::ToxicDmg => "toxicDmg",
+            DeadzoneStat::SuccessesPerDmg => "successesPerDmg",
+            DeadzoneStat::MaxDmg => "maxDmg",
+            DeadzoneStat::InstantKillMargin => "instantKillMargin",
+        }
+    }
+
+    // inclusive (min, max) of values the engine accepts, where i32::MAX means unbounded
+    pub fn stat_range(stat: DeadzoneStat) -> (i32, i32) {
+        match stat {
+            DeadzoneStat::Hp => (1, i32::MAX),
+            DeadzoneStat::NumDice => (0, i32::MAX),
+            DeadzoneStat::DiceStat => (1, 9), // 9+ can only succeed via house-rule hooks
+            DeadzoneStat::NumRerolls => (0, i32::MAX),
+            DeadzoneStat::NumDiceKept => (1, i32::MAX),
+            DeadzoneStat::MaxSuccesses => (0, i32::MAX),
+            DeadzoneStat::Ap => (0, i32::MAX),
+            DeadzoneStat::ApPerExcessSuccess => (0, i32::MAX),
+            DeadzoneStat::Armor => (0, i32::MAX),
+            DeadzoneStat::NumShieldDice => (0, i32::MAX),
+            DeadzoneStat::NumShieldRerolls => (0, i32::MAX),
+            DeadzoneStat::ShieldPipModifier => (-3, 5), // keeps shield dice needing 1+ to 9+, like diceStat
+            DeadzoneStat::ToxicDmg => (0, i32::MAX),
+            DeadzoneStat::SuccessesPerDmg => (1, i32::MAX),
+            DeadzoneStat::MaxDmg => (0, i32::MAX),
+            DeadzoneStat::InstantKillMargin => (1, i32::MAX),
+        }
+    }
+
+    pub fn face_success_values_in_range(values: &[i32; NUM_DIE_FACES]) -> bool {
+        let (min, max) = FACE_SUCCESS_VALUE_RANGE;
+        values.iter().all(|val| (min..=max).contains(val))
+    }
+
+    pub fn set_stat(&mut self, stat: DeadzoneStat, val: i32) {
+        match stat {
+            DeadzoneStat::Hp => self.hp = val,
+            DeadzoneStat::NumDice => self.num_dice = val,
+            DeadzoneStat::DiceStat => self.dice_stat = val,
+            DeadzoneStat::NumRerolls => self.num_rerolls = val,
+            DeadzoneStat::NumDiceKept => self.num_dice_kept = val,
+            DeadzoneStat::MaxSuccesses => self.max_successes = val,
+            DeadzoneStat::Ap => self.ap = val,
+            DeadzoneStat::ApPerExcessSuccess => self.ap_per_excess_success = val,
+            DeadzoneStat::Armor => self.armor = val,
+            DeadzoneStat::NumShieldDice => self.num_shield_dice = val,
+            DeadzoneStat::NumShieldRerolls => self.num_shield_rerolls = val,
+            DeadzoneStat::ShieldPipModifier => self.shield_pip_modifier = val,
+            DeadzoneStat::ToxicDmg => self.toxic_dmg = val,
+            DeadzoneStat::SuccessesPerDmg => self.successes_per_dmg = val,
+            DeadzoneStat::MaxDmg => self.max_dmg = val,
+            DeadzoneStat::InstantKillMargin => self.instant_kill_margin = val,
         }
     }
 }
+
+impl Default for DeadzoneModel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// lets JS hand over several models by reference; passing an array of models directly to wasm
+// would consume the JS objects
+#[wasm_bindgen]
+#[derive(Default)]
+pub struct DeadzoneModelList {
+    models: Vec<DeadzoneModel>,
+}
+
+#[wasm_bindgen]
+impl DeadzoneModelList {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> DeadzoneModelList {
+        Default::default()
+    }
+
+    // stores a copy, so the caller keeps ownership of the model
+    pub fn push(&mut self, model: &DeadzoneModel) {
+        self.models.push(*model);
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn length(&self) -> usize {
+        self.models.len()
+    }
+}
+
+impl DeadzoneModelList {
+    pub fn models(&self) -> &[DeadzoneModel] {
+        &self.models
+    }
+}
@@ -1,15 +1,76 @@
+use serde::{Deserialize, Serialize};
 use wasm_bindgen::prelude::*;
 
 #[wasm_bindgen]
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DeadzoneEdition {
+    Third,
+    Second, // shields and armor don't stack, and toxic needs at least 1 dmg to get through
+}
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
 pub struct DeadzoneOptions {
     #[wasm_bindgen(js_name = numSimulations)]
-    pub num_simulations: i32,
+    pub num_simulations: i32, // 0 means calculate exactly instead of simulating
     #[wasm_bindgen(js_name = numRounds)]
     pub num_rounds: i32,
+    // later rounds are skipped once either model is dead, instead of every round being rolled regardless
+    #[wasm_bindgen(js_name = stopWhenDead)]
+    pub stop_when_dead: bool,
     #[wasm_bindgen(js_name = attackerCanBeDamaged)]
     pub attacker_can_be_damaged: bool,
     #[wasm_bindgen(js_name = explodingDiceMaxLevels)]
     pub exploding_dice_max_levels: i32,
+    pub aimed: bool, // attacker took an Aim action, so gets +1 die
+    // friendly models in base contact with the target add 1 die each, but only to a Fight
+    #[wasm_bindgen(js_name = attackerNumAssists)]
+    pub attacker_num_assists: i32,
+    #[wasm_bindgen(js_name = defenderNumAssists)]
+    pub defender_num_assists: i32,
+    // Fight-only bonuses for an attacker that charged in, as extra dice and as lowering the stat needed
+    #[wasm_bindgen(js_name = chargeBonusDice)]
+    pub charge_bonus_dice: i32,
+    #[wasm_bindgen(js_name = chargePipBonus)]
+    pub charge_pip_bonus: i32,
+    // Fight-only extra dice for a defender that braced in a defensive stance
+    #[wasm_bindgen(js_name = defensiveStanceDice)]
+    pub defensive_stance_dice: i32,
+    // abilities that make the opponent reroll some of its successes after the roll; the dice with
+    // the most successes are picked, and the rerolled dice count however they land
+    #[wasm_bindgen(js_name = attackerForcedRerolls)]
+    pub attacker_forced_rerolls: i32,
+    #[wasm_bindgen(js_name = defenderForcedRerolls)]
+    pub defender_forced_rerolls: i32,
+    // for action sequences, each attack after the first rolls this many fewer dice and needs this
+    // many more pips than the one before it, like -1 die on the second attack and -2 on the third
+    #[wasm_bindgen(js_name = fatigueDicePenalty)]
+    pub fatigue_dice_penalty: i32,
+    #[wasm_bindgen(js_name = fatiguePipPenalty)]
+    pub fatigue_pip_penalty: i32,
+    pub edition: DeadzoneEdition,
+    // extra defender dice from cover, rolled on their own stat and added to the defender's successes;
+    // only for shooting, since cover doesn't help in a Fight
+    #[wasm_bindgen(js_name = numCoverDice)]
+    pub num_cover_dice: i32,
+    #[wasm_bindgen(js_name = coverDiceStat)]
+    pub cover_dice_stat: i32,
+    // 0 means plain sampling; otherwise each simulated die succeeds with this prob and samples get
+    // reweighted, which resolves rare high-success tails with far fewer simulations
+    #[wasm_bindgen(js_name = importanceSamplingSuccessProb)]
+    pub importance_sampling_success_prob: f64,
+    // when simulating, success counts within this much prob of either extreme get exact probs
+    // instead, so rare outcomes don't come back as 0 or as a handful of lucky samples
+    #[wasm_bindgen(js_name = exactTailMass)]
+    pub exact_tail_mass: f64,
+    // applied to returned dmg probs only, so every consumer shows the same numbers: buckets under
+    // the min prob are dropped and the rest renormalized, then probs are rounded; i32::MAX decimals
+    // means no rounding
+    #[wasm_bindgen(js_name = outputMinProb)]
+    pub output_min_prob: f64,
+    #[wasm_bindgen(js_name = outputDecimals)]
+    pub output_decimals: i32,
 }
 
 #[wasm_bindgen]
@@ -19,8 +80,32 @@ impl DeadzoneOptions {
         DeadzoneOptions {
             num_simulations: 100,
             num_rounds: 1,
+            stop_when_dead: false,
             attacker_can_be_damaged: false,
             exploding_dice_max_levels: i32::MAX,
+            aimed: false,
+            attacker_num_assists: 0,
+            defender_num_assists: 0,
+            charge_bonus_dice: 0,
+            charge_pip_bonus: 0,
+            defensive_stance_dice: 0,
+            attacker_forced_rerolls: 0,
+            defender_forced_rerolls: 0,
+            fatigue_dice_penalty: 0,
+            fatigue_pip_penalty: 0,
+            edition: DeadzoneEdition::Third,
+            num_cover_dice: 0,
+            cover_dice_stat: 5,
+            importance_sampling_success_prob: 0.0,
+            exact_tail_mass: 0.0,
+            output_min_prob: 0.0,
+            output_decimals: i32::MAX,
         }
     }
 }
+
+impl Default for DeadzoneOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,156 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
+use wasm_bindgen::prelude::*;
+
+use super::deadzone_model::DeadzoneModel;
+use super::deadzone_options::DeadzoneOptions;
+use super::simulator::calc_single_round_dmg_probs;
+use super::validation::validate_inputs;
+use crate::common::calc_error::CalcError;
+use crate::common::ts_types::{result_schema_v1, RESULT_SCHEMA_VERSION};
+use crate::common::{add_to_map_value, expected_value};
+
+// how a model picks its action each activation
+#[wasm_bindgen]
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum DeadzoneDuelPolicy {
+    AlwaysShoot,
+    AlwaysFight,
+    // whichever of shooting or fighting has the higher expected dmg dealt minus dmg taken
+    BestExpectedDmg,
+}
+
+#[derive(Tsify, Serialize, Deserialize)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+#[serde(rename_all = "camelCase")]
+pub struct DuelOutcomeProbs {
+    #[serde(default = "result_schema_v1")]
+    pub result_schema_version: u32,
+    pub both_survive: f64,
+    pub only_first_survives: f64,
+    pub only_second_survives: f64,
+}
+
+// the first model activates first each turn, then the second, for num_turns turns; each activation is
+// one shot or fight against the other model, and the duel stops as soon as either model is dead
+#[wasm_bindgen(js_name = "deadzoneCalcDuel")]
+pub fn deadzone_calc_duel(
+    first: &DeadzoneModel,
+    second: &DeadzoneModel,
+    options: &DeadzoneOptions,
+    num_turns: i32,
+    first_policy: DeadzoneDuelPolicy,
+    second_policy: DeadzoneDuelPolicy,
+) -> Result<DuelOutcomeProbs, CalcError> {
+    validate_inputs(first, second, options)?;
+    // signed dmg from the actor's point of view, so negative dmg is dealt to the actor while fighting
+    let first_outcome_probs = calc_activation_outcome_probs(first, second, options, first_policy);
+    let second_outcome_probs = calc_activation_outcome_probs(second, first, options, second_policy);
+
+    // (first's dmg taken, second's dmg taken), each capped at hp
+    let mut state_probs = HashMap::from([((0, 0), 1.0)]);
+    for _ in 0..num_turns {
+        state_probs = activate(
+            &state_probs,
+            &first_outcome_probs,
+            first.hp,
+            second.hp,
+            false,
+        );
+        state_probs = activate(
+            &state_probs,
+            &second_outcome_probs,
+            first.hp,
+            second.hp,
+            true,
+        );
+    }
+
+    let mut outcome_probs = DuelOutcomeProbs {
+        result_schema_version: RESULT_SCHEMA_VERSION,
+        both_survive: 0.0,
+        only_first_survives: 0.0,
+        only_second_survives: 0.0,
+    };
+    // each roll only damages one side and the duel stops at the first death, so both can't die
+    for ((first_dmg_taken, second_dmg_taken), prob) in state_probs.iter() {
+        let outcome_prob = if *first_dmg_taken >= first.hp {
+            &mut outcome_probs.only_second_survives
+        } else if *second_dmg_taken >= second.hp {
+            &mut outcome_probs.only_first_survives
+        } else {
+            &mut outcome_probs.both_survive
+        };
+        *outcome_prob += prob;
+    }
+    Ok(outcome_probs)
+}
+
+// policies only look at the models, not the wounds so far, so each model's activation is the same every turn
+fn calc_activation_outcome_probs(
+    actor: &DeadzoneModel,
+    target: &DeadzoneModel,
+    options: &DeadzoneOptions,
+    policy: DeadzoneDuelPolicy,
+) -> HashMap<i32, f64> {
+    let action_probs = |is_fight: bool| {
+        let mut action_options = *options;
+        action_options.num_rounds = 1;
+        action_options.aimed = false;
+        action_options.attacker_can_be_damaged = is_fight;
+        calc_single_round_dmg_probs(actor, target, &action_options)
+    };
+    match policy {
+        DeadzoneDuelPolicy::AlwaysShoot => action_probs(false),
+        DeadzoneDuelPolicy::AlwaysFight => action_probs(true),
+        DeadzoneDuelPolicy::BestExpectedDmg => {
+            // signed dmg's expected value is already dmg dealt minus dmg taken
+            let shoot_probs = action_probs(false);
+            let fight_probs = action_probs(true);
+            if expected_value(&fight_probs) > expected_value(&shoot_probs) {
+                fight_probs
+            } else {
+                shoot_probs
+            }
+        }
+    }
+}
+
+fn activate(
+    state_probs: &HashMap<(i32, i32), f64>,
+    outcome_probs: &HashMap<i32, f64>,
+    first_hp: i32,
+    second_hp: i32,
+    is_second_acting: bool,
+) -> HashMap<(i32, i32), f64> {
+    let mut next_state_probs = HashMap::new();
+    for ((first_dmg_taken, second_dmg_taken), state_prob) in state_probs.iter() {
+        if *first_dmg_taken >= first_hp || *second_dmg_taken >= second_hp {
+            add_to_map_value(
+                &mut next_state_probs,
+                &(*first_dmg_taken, *second_dmg_taken),
+                *state_prob,
+            );
+            continue;
+        }
+        for (outcome, outcome_prob) in outcome_probs.iter() {
+            let (dmg_to_first, dmg_to_second) = if is_second_acting {
+                (std::cmp::max(0, *outcome), std::cmp::max(0, -outcome))
+            } else {
+                (std::cmp::max(0, -outcome), std::cmp::max(0, *outcome))
+            };
+            let next_state = (
+                std::cmp::min(first_hp, first_dmg_taken + dmg_to_first),
+                std::cmp::min(second_hp, second_dmg_taken + dmg_to_second),
+            );
+            add_to_map_value(
+                &mut next_state_probs,
+                &next_state,
+                state_prob * outcome_prob,
+            );
+        }
+    }
+    next_state_probs
+}
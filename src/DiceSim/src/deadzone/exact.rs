@@ -0,0 +1,385 @@
+use std::collections::HashMap;
+
+use super::house_rules::{adjust_success_counts, pip_success_values};
+use super::roll_params::{RollParams, PIP_HI};
+use crate::common::calc_error::CalcError;
+use crate::common::{add_to_map_value, explosion_chain_pmf};
+
+const EXPLODE_PROB: f64 = 1.0 / PIP_HI as f64;
+// explosion chains longer than this have prob below 1e-15, so longer ones are lumped in here
+const MAX_TRACKED_EXPLOSIONS: i32 = 16;
+// a step is one state of the enumeration meeting one outcome of one more die; past this many,
+// calculating exactly would hang the UI for a long time, like simulating too many die rolls would
+const MAX_EXACT_STEPS: f64 = 1e9;
+// dice times the rolls each one's explosion chain is tracked for, so the states' success and failure
+// counts stay small; anything past it is over the step budget anyway, this just says so sooner
+const MAX_EXACT_DIE_ROLLS: i64 = 2_000;
+
+// rejects roll params whose exact enumeration would take more than MAX_EXACT_STEPS, before any of it
+// starts; the count is an upper bound worked out from a single die's outcomes, since ranking dice
+// makes the number of states grow combinatorially with how many dice are tracked
+pub fn check_exact_budget(roll_params: &RollParams) -> Result<(), CalcError> {
+    let max_rolls_per_die = roll_params
+        .exploding_dice_max_levels
+        .clamp(0, MAX_TRACKED_EXPLOSIONS) as i64
+        + 1;
+    let num_die_rolls = std::cmp::max(0, roll_params.num_dice) as i64
+        + std::cmp::max(0, roll_params.num_rerolls) as i64;
+    if num_die_rolls * max_rolls_per_die > MAX_EXACT_DIE_ROLLS {
+        return Err(CalcError::BudgetExceeded);
+    }
+
+    let single_die_probs = calc_single_die_sf_probs(roll_params);
+    let num_die_outcomes = single_die_probs.len() as f64;
+    let die_max_successes = single_die_probs.keys().map(|(s, _)| *s).max().unwrap_or(0) as f64;
+    let die_max_failures = single_die_probs.keys().map(|(_, f)| *f).max().unwrap_or(0) as f64;
+    let num_dice = std::cmp::max(0, roll_params.num_dice) as f64;
+    let num_rerolls = std::cmp::max(0, roll_params.num_rerolls) as f64;
+    // (successes, failures) pairs k dice can roll
+    let num_sf_states = |k: f64| (k * die_max_successes + 1.0) * (k * die_max_failures + 1.0);
+
+    let num_steps = if roll_params.ranks_dice() {
+        let num_forced_rerolls = std::cmp::max(0, roll_params.num_forced_rerolls) as f64;
+        let keeps_untracked = !roll_params.drops_dice();
+        let num_tracked = if keeps_untracked {
+            num_forced_rerolls
+        } else {
+            std::cmp::max(0, roll_params.num_dice_kept) as f64 + num_forced_rerolls
+        };
+        let num_tracked = num_tracked.min(num_dice + num_rerolls);
+        // sorted vecs of up to num_tracked dice, each die scoring one of the distinct success values
+        let num_distinct_successes = single_die_probs
+            .keys()
+            .map(|(s, _)| *s)
+            .collect::<std::collections::HashSet<_>>()
+            .len() as f64;
+        let mut num_ranked_dice = 1.0;
+        let mut i = 1.0;
+        while i <= num_tracked && num_ranked_dice <= MAX_EXACT_STEPS {
+            num_ranked_dice *= (num_distinct_successes + i) / i;
+            i += 1.0;
+        }
+        let num_untracked_sums = if keeps_untracked {
+            (num_dice + num_rerolls) * die_max_successes + 1.0
+        } else {
+            1.0
+        };
+        let num_states = num_ranked_dice * num_untracked_sums * (num_dice * die_max_failures + 1.0);
+        num_states * num_die_outcomes * (num_dice + num_rerolls + num_forced_rerolls)
+    } else {
+        let max_pool_size = num_dice.max(num_rerolls);
+        let num_pool_states = num_sf_states(max_pool_size);
+        max_pool_size * num_pool_states * num_die_outcomes
+            + num_sf_states(num_dice) * num_pool_states
+    };
+    if num_steps > MAX_EXACT_STEPS {
+        return Err(CalcError::BudgetExceeded);
+    }
+    Ok(())
+}
+
+// exact counterpart of rolling roll_params.num_dice in the simulator, including rerolling failures;
+// like the simulator, failed explosion dice count as failures that can be rerolled
+pub fn calc_exact_success_probs(roll_params: &RollParams) -> HashMap<i32, f64> {
+    let single_die_probs = calc_single_die_sf_probs(roll_params);
+    if roll_params.ranks_dice() {
+        let success_probs = calc_exact_ranked_success_probs(roll_params, &single_die_probs);
+        return adjust_success_counts(success_probs, roll_params);
+    }
+    let max_pool_size = std::cmp::max(roll_params.num_dice, roll_params.num_rerolls);
+
+    // pool_sf_probs[k] is the joint (successes, failures) distribution of rolling k dice
+    let mut pool_sf_probs = vec![HashMap::from([((0, 0), 1.0)])];
+    for _ in 0..max_pool_size {
+        let next_pool_sf_probs =
+            convolve_sf_probs(pool_sf_probs.last().unwrap(), &single_die_probs);
+        pool_sf_probs.push(next_pool_sf_probs);
+    }
+
+    let mut success_probs = HashMap::<i32, f64>::new();
+    let num_dice = std::cmp::max(0, roll_params.num_dice) as usize;
+    for ((successes, failures), prob) in pool_sf_probs[num_dice].iter() {
+        let num_actual_rerolls =
+            std::cmp::max(0, std::cmp::min(roll_params.num_rerolls, *failures));
+        for ((rerolled_successes, _), reroll_prob) in
+            pool_sf_probs[num_actual_rerolls as usize].iter()
+        {
+            add_to_map_value(
+                &mut success_probs,
+                &(successes + rerolled_successes),
+                prob * reroll_prob,
+            );
+        }
+    }
+    adjust_success_counts(success_probs, roll_params)
+}
+
+// exact counterpart of ranking dice in the simulator, to keep only the best ones or to have the
+// opponent force rerolls of the best successes. the best dice so far are tracked as a vec sorted
+// from most successes down, and only as many as could still be force rerolled or kept; when every
+// die is kept, the successes of dice past the tracked ones are just summed
+fn calc_exact_ranked_success_probs(
+    roll_params: &RollParams,
+    single_die_probs: &HashMap<(i32, i32), f64>,
+) -> HashMap<i32, f64> {
+    let num_dice_kept = std::cmp::max(0, roll_params.num_dice_kept) as usize;
+    let num_forced_rerolls = std::cmp::max(0, roll_params.num_forced_rerolls) as usize;
+    let keeps_untracked = !roll_params.drops_dice();
+    let num_tracked = if keeps_untracked {
+        num_forced_rerolls
+    } else {
+        num_dice_kept.saturating_add(num_forced_rerolls)
+    };
+    // (best dice, successes of kept dice that aren't tracked) with one more die, tracking num_kept
+    let keep_best =
+        |(best_dice, untracked_successes): &(Vec<i32>, i32), successes: i32, num_kept: usize| {
+            let mut next_best_dice = best_dice.clone();
+            let insert_at = next_best_dice.partition_point(|kept| *kept >= successes);
+            next_best_dice.insert(insert_at, successes);
+            let next_untracked_successes = if keeps_untracked {
+                untracked_successes + next_best_dice.iter().skip(num_kept).sum::<i32>()
+            } else {
+                0
+            };
+            next_best_dice.truncate(num_kept);
+            (next_best_dice, next_untracked_successes)
+        };
+
+    // (ranked dice, failures) after rolling the original dice; vecs aren't Copy, so no add_to_map_value
+    let mut roll_states = HashMap::from([(((Vec::<i32>::new(), 0), 0), 1.0)]);
+    for _ in 0..roll_params.num_dice {
+        let mut next_roll_states = HashMap::new();
+        for ((ranked_dice, failures), prob) in roll_states.iter() {
+            for ((successes, die_failures), die_prob) in single_die_probs.iter() {
+                *next_roll_states
+                    .entry((
+                        keep_best(ranked_dice, *successes, num_tracked),
+                        failures + die_failures,
+                    ))
+                    .or_insert(0.0) += prob * die_prob;
+            }
+        }
+        roll_states = next_roll_states;
+    }
+
+    // (ranked dice, rerolls still to roll), rolling one reroll per pass until none are left
+    let mut reroll_states = HashMap::new();
+    for ((ranked_dice, failures), prob) in roll_states {
+        let num_actual_rerolls = std::cmp::max(0, std::cmp::min(roll_params.num_rerolls, failures));
+        *reroll_states
+            .entry((ranked_dice, num_actual_rerolls))
+            .or_insert(0.0) += prob;
+    }
+    let mut ranked_states = HashMap::new();
+    while !reroll_states.is_empty() {
+        let mut next_reroll_states = HashMap::new();
+        for ((ranked_dice, rerolls_left), prob) in reroll_states {
+            if rerolls_left == 0 {
+                *ranked_states.entry(ranked_dice).or_insert(0.0) += prob;
+                continue;
+            }
+            for ((successes, _), die_prob) in single_die_probs.iter() {
+                *next_reroll_states
+                    .entry((
+                        keep_best(&ranked_dice, *successes, num_tracked),
+                        rerolls_left - 1,
+                    ))
+                    .or_insert(0.0) += prob * die_prob;
+            }
+        }
+        reroll_states = next_reroll_states;
+    }
+
+    // the forced rerolls replace the best dice that have successes, and can't be rerolled again
+    let mut success_probs = HashMap::<i32, f64>::new();
+    for ((best_dice, untracked_successes), prob) in ranked_states {
+        let num_actual_forced_rerolls = best_dice
+            .iter()
+            .take(num_forced_rerolls)
+            .filter(|successes| **successes > 0)
+            .count();
+        let mut kept_dice = best_dice[num_actual_forced_rerolls..].to_vec();
+        kept_dice.truncate(num_dice_kept);
+        let mut kept_states = HashMap::from([((kept_dice, untracked_successes), prob)]);
+        for _ in 0..num_actual_forced_rerolls {
+            let mut next_kept_states = HashMap::new();
+            for (kept, kept_prob) in kept_states.iter() {
+                for ((successes, _), die_prob) in single_die_probs.iter() {
+                    *next_kept_states
+                        .entry(keep_best(kept, *successes, num_dice_kept))
+                        .or_insert(0.0) += kept_prob * die_prob;
+                }
+            }
+            kept_states = next_kept_states;
+        }
+        for ((kept_dice, untracked_successes), kept_prob) in kept_states {
+            add_to_map_value(
+                &mut success_probs,
+                &(kept_dice.iter().sum::<i32>() + untracked_successes),
+                kept_prob,
+            );
+        }
+    }
+    success_probs
+}
+
+// joint (successes, failures) distribution of a single die and its explosion chain
+fn calc_single_die_sf_probs(roll_params: &RollParams) -> HashMap<(i32, i32), f64> {
+    let max_explosions = roll_params
+        .exploding_dice_max_levels
+        .clamp(0, MAX_TRACKED_EXPLOSIONS);
+    let success_value_by_pip = pip_success_values(roll_params);
+    let pip_sf = |pip: i32| match success_value_by_pip[pip as usize] {
+        0 => (0, 1),
+        success_value => (success_value, 0),
+    };
+    let (eight_successes, eight_failures) = pip_sf(PIP_HI);
+
+    let mut sf_probs = HashMap::<(i32, i32), f64>::new();
+    for num_explosions in 0..=max_explosions {
+        let chain_prob = explosion_chain_pmf(num_explosions, EXPLODE_PROB, max_explosions);
+        // final roll of an uncapped chain is known not to be an 8, but a capped chain's final roll can be anything
+        let final_pip_hi = if num_explosions == max_explosions {
+            PIP_HI
+        } else {
+            PIP_HI - 1
+        };
+        for final_pip in 1..=final_pip_hi {
+            let (final_successes, final_failures) = pip_sf(final_pip);
+            add_to_map_value(
+                &mut sf_probs,
+                &(
+                    num_explosions * eight_successes + final_successes,
+                    num_explosions * eight_failures + final_failures,
+                ),
+                chain_prob / final_pip_hi as f64,
+            );
+        }
+    }
+    sf_probs
+}
+
+fn convolve_sf_probs(
+    probs_a: &HashMap<(i32, i32), f64>,
+    probs_b: &HashMap<(i32, i32), f64>,
+) -> HashMap<(i32, i32), f64> {
+    let mut sum_probs = HashMap::<(i32, i32), f64>::new();
+    for ((s_a, f_a), prob_a) in probs_a.iter() {
+        for ((s_b, f_b), prob_b) in probs_b.iter() {
+            add_to_map_value(&mut sum_probs, &(s_a + s_b, f_a + f_b), prob_a * prob_b);
+        }
+    }
+    sum_probs
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use super::*;
+    use crate::deadzone::deadzone_model::DeadzoneModel;
+    use crate::deadzone::deadzone_options::DeadzoneOptions;
+    use crate::deadzone::roll_params::PIP_LO;
+    use crate::deadzone::simulator::{roll_success_counts, success_counts_to_probs};
+    use crate::deadzone::validation::validate_inputs;
+
+    const NUM_SIMULATIONS: i32 = 100_000;
+
+    fn forced_reroll_params(
+        num_dice: i32,
+        num_dice_kept: i32,
+        num_forced_rerolls: i32,
+    ) -> RollParams {
+        let mut model = DeadzoneModel::new();
+        model.num_dice = num_dice;
+        model.num_rerolls = 1;
+        model.num_dice_kept = num_dice_kept;
+        let mut options = DeadzoneOptions::new();
+        options.num_simulations = NUM_SIMULATIONS;
+        options.attacker_forced_rerolls = num_forced_rerolls;
+        RollParams::new(&model, true, &options)
+    }
+
+    #[test]
+    fn forced_reroll_of_a_lone_success_succeeds_again() {
+        let mut roll_params = forced_reroll_params(1, i32::MAX, 1);
+        roll_params.num_rerolls = 0;
+        roll_params.exploding_dice_max_levels = 0;
+        // a 5+ succeeds half the time, and only a success is forced to roll again
+        let success_probs = calc_exact_success_probs(&roll_params);
+        assert!((success_probs[&1] - 0.25).abs() < 1e-12);
+        assert!((success_probs[&0] - 0.75).abs() < 1e-12);
+    }
+
+    // tracking the best 25 dice of 40 has far too many states to enumerate, but simulating is fine
+    #[test]
+    fn too_many_ranked_dice_exceed_the_exact_budget() {
+        assert_eq!(
+            check_exact_budget(&forced_reroll_params(40, 20, 5)),
+            Err(CalcError::BudgetExceeded)
+        );
+        assert_eq!(check_exact_budget(&forced_reroll_params(4, 2, 1)), Ok(()));
+
+        let mut model = DeadzoneModel::new();
+        model.num_dice = 40;
+        model.num_dice_kept = 20;
+        let mut options = DeadzoneOptions::new();
+        options.attacker_forced_rerolls = 5;
+        assert_eq!(validate_inputs(&model, &model, &options), Ok(()));
+        options.num_simulations = 0;
+        assert_eq!(
+            validate_inputs(&model, &model, &options),
+            Err(CalcError::BudgetExceeded)
+        );
+    }
+
+    // 150 dice with 16 tracked explosions each are too many to enumerate, but not without explosions
+    #[test]
+    fn dice_times_explosion_depth_is_bounded() {
+        let mut model = DeadzoneModel::new();
+        model.num_dice = 150;
+        let mut options = DeadzoneOptions::new();
+        options.num_simulations = 0;
+        assert_eq!(
+            check_exact_budget(&RollParams::new(&model, true, &options)),
+            Err(CalcError::BudgetExceeded)
+        );
+        options.exploding_dice_max_levels = 0;
+        assert_eq!(validate_inputs(&model, &model, &options), Ok(()));
+
+        // cover dice are calculated exactly even when the models' dice are simulated
+        options.num_simulations = 100;
+        options.num_cover_dice = 3_000;
+        assert_eq!(
+            validate_inputs(&model, &model, &options),
+            Err(CalcError::BudgetExceeded)
+        );
+    }
+
+    #[test]
+    fn forced_rerolls_match_simulation() {
+        // every die kept, and only the best two kept
+        for roll_params in [
+            forced_reroll_params(4, i32::MAX, 2),
+            forced_reroll_params(4, 2, 1),
+        ] {
+            let exact_probs = calc_exact_success_probs(&roll_params);
+            let mut rng = StdRng::seed_from_u64(crate::self_test::SELF_TEST_SEED);
+            let die_distribution = rand::distributions::Uniform::new(PIP_LO, PIP_HI + 1);
+            let simulated_probs = success_counts_to_probs(
+                &roll_success_counts(&die_distribution, &mut rng, &roll_params),
+                NUM_SIMULATIONS,
+            );
+            assert!((exact_probs.values().sum::<f64>() - 1.0).abs() < 1e-9);
+            for successes in exact_probs.keys().chain(simulated_probs.keys()) {
+                let exact_prob = exact_probs.get(successes).unwrap_or(&0.0);
+                let simulated_prob = simulated_probs.get(successes).unwrap_or(&0.0);
+                assert!(
+                    (exact_prob - simulated_prob).abs() < 0.01,
+                    "{successes} successes: exact {exact_prob}, simulated {simulated_prob}"
+                );
+            }
+        }
+    }
+}
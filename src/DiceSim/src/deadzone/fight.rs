@@ -0,0 +1,175 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
+use wasm_bindgen::prelude::*;
+
+use super::deadzone_model::DeadzoneModel;
+use super::deadzone_options::DeadzoneOptions;
+use super::simulator::calc_single_round_dmg_probs;
+use super::validation::validate_inputs;
+use crate::common::calc_error::CalcError;
+use crate::common::ts_types::{result_schema_v1, ProbMap, RESULT_SCHEMA_VERSION};
+use crate::common::{add_to_map_value, expected_value};
+
+#[derive(Tsify, Serialize, Deserialize)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+#[serde(rename_all = "camelCase")]
+pub struct FightOutcomeProbs {
+    #[serde(default = "result_schema_v1")]
+    pub result_schema_version: u32,
+    pub both_die: f64,
+    pub only_attacker_dies: f64,
+    pub only_defender_dies: f64,
+    pub neither_dies: f64,
+}
+
+#[wasm_bindgen(js_name = "deadzoneCalcFightOutcomeProbs")]
+pub fn deadzone_calc_fight_outcome_probs(
+    attacker: &DeadzoneModel,
+    defender: &DeadzoneModel,
+    options: &DeadzoneOptions,
+) -> Result<FightOutcomeProbs, CalcError> {
+    validate_inputs(attacker, defender, options)?;
+    let mut outcome_probs = FightOutcomeProbs {
+        result_schema_version: RESULT_SCHEMA_VERSION,
+        both_die: 0.0,
+        only_attacker_dies: 0.0,
+        only_defender_dies: 0.0,
+        neither_dies: 0.0,
+    };
+
+    for ((atk_dmg_taken, def_dmg_taken), prob) in
+        calc_joint_fight_dmg_probs(attacker, defender, options).iter()
+    {
+        let attacker_dies = *atk_dmg_taken >= attacker.hp;
+        let defender_dies = *def_dmg_taken >= defender.hp;
+        let outcome_prob = match (attacker_dies, defender_dies) {
+            (true, true) => &mut outcome_probs.both_die,
+            (true, false) => &mut outcome_probs.only_attacker_dies,
+            (false, true) => &mut outcome_probs.only_defender_dies,
+            (false, false) => &mut outcome_probs.neither_dies,
+        };
+        *outcome_prob += prob;
+    }
+    Ok(outcome_probs)
+}
+
+// probs[i][j] is the prob of the attacker taking i dmg and the defender taking j dmg over all rounds,
+// with i running 0 to the attacker's hp and j running 0 to the defender's hp, since dmg taken is capped at hp
+#[derive(Tsify, Serialize, Deserialize)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+#[serde(rename_all = "camelCase")]
+pub struct JointDmgProbs {
+    #[serde(default = "result_schema_v1")]
+    pub result_schema_version: u32,
+    pub probs: Vec<Vec<f64>>,
+}
+
+#[wasm_bindgen(js_name = "deadzoneCalcJointDmgProbs")]
+pub fn deadzone_calc_joint_dmg_probs(
+    attacker: &DeadzoneModel,
+    defender: &DeadzoneModel,
+    options: &DeadzoneOptions,
+) -> Result<JointDmgProbs, CalcError> {
+    validate_inputs(attacker, defender, options)?;
+    let mut probs = vec![
+        vec![0.0; std::cmp::max(0, defender.hp) as usize + 1];
+        std::cmp::max(0, attacker.hp) as usize + 1
+    ];
+    for ((atk_dmg_taken, def_dmg_taken), prob) in
+        calc_joint_fight_dmg_probs(attacker, defender, options).iter()
+    {
+        probs[*atk_dmg_taken as usize][*def_dmg_taken as usize] += prob;
+    }
+    Ok(JointDmgProbs {
+        result_schema_version: RESULT_SCHEMA_VERSION,
+        probs,
+    })
+}
+
+// wounds each model has left after all rounds, 0 meaning dead, for attrition planning alongside kill probs
+#[derive(Tsify, Serialize, Deserialize)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+#[serde(rename_all = "camelCase")]
+pub struct WoundsRemaining {
+    #[serde(default = "result_schema_v1")]
+    pub result_schema_version: u32,
+    pub attacker_expected_wounds: f64,
+    pub defender_expected_wounds: f64,
+    pub attacker_wound_probs: ProbMap,
+    pub defender_wound_probs: ProbMap,
+}
+
+#[wasm_bindgen(js_name = "deadzoneCalcWoundsRemaining")]
+pub fn deadzone_calc_wounds_remaining(
+    attacker: &DeadzoneModel,
+    defender: &DeadzoneModel,
+    options: &DeadzoneOptions,
+) -> Result<WoundsRemaining, CalcError> {
+    validate_inputs(attacker, defender, options)?;
+    let mut attacker_wound_probs = HashMap::<i32, f64>::new();
+    let mut defender_wound_probs = HashMap::<i32, f64>::new();
+    for ((atk_dmg_taken, def_dmg_taken), prob) in
+        calc_joint_fight_dmg_probs(attacker, defender, options).iter()
+    {
+        add_to_map_value(
+            &mut attacker_wound_probs,
+            &(attacker.hp - atk_dmg_taken),
+            *prob,
+        );
+        add_to_map_value(
+            &mut defender_wound_probs,
+            &(defender.hp - def_dmg_taken),
+            *prob,
+        );
+    }
+    Ok(WoundsRemaining {
+        result_schema_version: RESULT_SCHEMA_VERSION,
+        attacker_expected_wounds: expected_value(&attacker_wound_probs),
+        defender_expected_wounds: expected_value(&defender_wound_probs),
+        attacker_wound_probs: ProbMap(attacker_wound_probs),
+        defender_wound_probs: ProbMap(defender_wound_probs),
+    })
+}
+
+// joint distribution of (dmg taken by attacker, dmg taken by defender) after all rounds;
+// like the rest of the multi-round math, every round is fought even if a model is already dead unless
+// the options' stopWhenDead is set, and dmg taken is capped at hp since more than that can't change who died
+pub fn calc_joint_fight_dmg_probs(
+    attacker: &DeadzoneModel,
+    defender: &DeadzoneModel,
+    options: &DeadzoneOptions,
+) -> HashMap<(i32, i32), f64> {
+    let single_round_dmg_probs = calc_single_round_dmg_probs(attacker, defender, options);
+    let mut joint_dmg_probs = HashMap::from([((0, 0), 1.0)]);
+
+    for _round_number in 1..=std::cmp::max(1, options.num_rounds) {
+        let mut next_joint_dmg_probs = HashMap::<(i32, i32), f64>::new();
+        for ((atk_dmg_taken, def_dmg_taken), joint_prob) in joint_dmg_probs.iter() {
+            if options.stop_when_dead
+                && (*atk_dmg_taken >= attacker.hp || *def_dmg_taken >= defender.hp)
+            {
+                add_to_map_value(
+                    &mut next_joint_dmg_probs,
+                    &(*atk_dmg_taken, *def_dmg_taken),
+                    *joint_prob,
+                );
+                continue;
+            }
+            for (dmg, round_prob) in single_round_dmg_probs.iter() {
+                let next_atk_dmg_taken =
+                    std::cmp::min(attacker.hp, atk_dmg_taken + std::cmp::max(0, -dmg));
+                let next_def_dmg_taken =
+                    std::cmp::min(defender.hp, def_dmg_taken + std::cmp::max(0, *dmg));
+                add_to_map_value(
+                    &mut next_joint_dmg_probs,
+                    &(next_atk_dmg_taken, next_def_dmg_taken),
+                    joint_prob * round_prob,
+                );
+            }
+        }
+        joint_dmg_probs = next_joint_dmg_probs;
+    }
+    joint_dmg_probs
+}
@@ -0,0 +1,191 @@
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
+use wasm_bindgen::prelude::*;
+
+use super::deadzone_model::{DeadzoneModel, DeadzoneStat};
+use super::deadzone_options::DeadzoneOptions;
+use super::matrix::ThreatCell;
+use super::simulator::calc_dmg_probs;
+use super::validation::validate_inputs;
+use crate::common::calc_error::CalcError;
+use crate::common::ts_types::{result_schema_v1, RESULT_SCHEMA_VERSION};
+use crate::common::{expected_value, prob_at_least};
+
+// every cell is a full calculation, so a grid past this many would hang the UI for a long time
+const MAX_HEAT_MAP_CELLS: i64 = 10_000;
+
+// one stat of one side to vary, over min..=max
+#[wasm_bindgen]
+#[derive(Clone, Copy)]
+pub struct StatAxis {
+    pub stat: DeadzoneStat,
+    #[wasm_bindgen(js_name = isAttacker)]
+    pub is_attacker: bool,
+    pub min: i32,
+    pub max: i32,
+}
+
+#[wasm_bindgen]
+impl StatAxis {
+    #[wasm_bindgen(constructor)]
+    pub fn new(stat: DeadzoneStat, is_attacker: bool, min: i32, max: i32) -> StatAxis {
+        StatAxis {
+            stat,
+            is_attacker,
+            min,
+            max,
+        }
+    }
+}
+
+// cells[i][j] is for y_values[i] and x_values[j], so rows go along the y axis
+#[derive(Tsify, Serialize, Deserialize)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+#[serde(rename_all = "camelCase")]
+pub struct HeatMap {
+    #[serde(default = "result_schema_v1")]
+    pub result_schema_version: u32,
+    pub x_values: Vec<i32>,
+    pub y_values: Vec<i32>,
+    pub cells: Vec<Vec<ThreatCell>>,
+}
+
+// both axes can be the same side, like numDice × diceStat of the attacker;
+// if both axes are the same stat of the same side, the y axis wins
+#[wasm_bindgen(js_name = "deadzoneCalcHeatMap")]
+pub fn deadzone_calc_heat_map(
+    attacker: &DeadzoneModel,
+    defender: &DeadzoneModel,
+    options: &DeadzoneOptions,
+    x_axis: &StatAxis,
+    y_axis: &StatAxis,
+) -> Result<HeatMap, CalcError> {
+    if axis_len(x_axis)? * axis_len(y_axis)? > MAX_HEAT_MAP_CELLS {
+        return Err(CalcError::BudgetExceeded);
+    }
+    let x_values: Vec<i32> = (x_axis.min..=x_axis.max).collect();
+    let y_values: Vec<i32> = (y_axis.min..=y_axis.max).collect();
+
+    // still validated cell by cell, since varying one stat can break limits that depend on others
+    let cells: Vec<Vec<ThreatCell>> = y_values
+        .iter()
+        .map(|y_value| {
+            x_values
+                .iter()
+                .map(|x_value| {
+                    let (varied_attacker, varied_defender) =
+                        vary_models(attacker, defender, [(x_axis, *x_value), (y_axis, *y_value)]);
+                    validate_inputs(&varied_attacker, &varied_defender, options)?;
+                    let dmg_probs = calc_dmg_probs(&varied_attacker, &varied_defender, options);
+                    Ok(ThreatCell {
+                        expected_dmg: expected_value(&dmg_probs),
+                        kill_prob: prob_at_least(&dmg_probs, varied_defender.hp),
+                    })
+                })
+                .collect()
+        })
+        .collect::<Result<_, CalcError>>()?;
+
+    Ok(HeatMap {
+        result_schema_version: RESULT_SCHEMA_VERSION,
+        x_values,
+        y_values,
+        cells,
+    })
+}
+
+// an axis has to span at least one value, all of them within its stat's range
+fn axis_len(axis: &StatAxis) -> Result<i64, CalcError> {
+    let (min, max) = DeadzoneModel::stat_range(axis.stat);
+    if axis.min > axis.max || axis.min < min || axis.max > max {
+        return Err(CalcError::InvalidStat);
+    }
+    Ok(axis.max as i64 - axis.min as i64 + 1)
+}
+
+// later axes overwrite earlier ones when they vary the same stat of the same side
+fn vary_models(
+    attacker: &DeadzoneModel,
+    defender: &DeadzoneModel,
+    axis_values: [(&StatAxis, i32); 2],
+) -> (DeadzoneModel, DeadzoneModel) {
+    let mut varied_attacker = *attacker;
+    let mut varied_defender = *defender;
+    for (axis, value) in axis_values {
+        let model = if axis.is_attacker {
+            &mut varied_attacker
+        } else {
+            &mut varied_defender
+        };
+        model.set_stat(axis.stat, value);
+    }
+    (varied_attacker, varied_defender)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn grid_has_a_cell_per_axis_value_pair() {
+        let attacker = DeadzoneModel::new();
+        let defender = DeadzoneModel::new();
+        let mut options = DeadzoneOptions::new();
+        options.num_simulations = 0;
+        let x_axis = StatAxis::new(DeadzoneStat::NumDice, true, 1, 4);
+        let y_axis = StatAxis::new(DeadzoneStat::Armor, false, 0, 2);
+        let heat_map =
+            deadzone_calc_heat_map(&attacker, &defender, &options, &x_axis, &y_axis).unwrap();
+
+        assert_eq!(heat_map.x_values, vec![1, 2, 3, 4]);
+        assert_eq!(heat_map.y_values, vec![0, 1, 2]);
+        assert_eq!(heat_map.cells.len(), 3);
+        for (y_value, row) in heat_map.y_values.iter().zip(heat_map.cells.iter()) {
+            assert_eq!(row.len(), 4);
+            for (x_value, cell) in heat_map.x_values.iter().zip(row.iter()) {
+                let (varied_attacker, varied_defender) = vary_models(
+                    &attacker,
+                    &defender,
+                    [(&x_axis, *x_value), (&y_axis, *y_value)],
+                );
+                let dmg_probs = calc_dmg_probs(&varied_attacker, &varied_defender, &options);
+                let total: f64 = dmg_probs.values().sum();
+                assert!((total - 1.0).abs() < 1e-9);
+                assert!(
+                    (cell.kill_prob - prob_at_least(&dmg_probs, varied_defender.hp)).abs() < 1e-12
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn out_of_range_axis_values_are_rejected() {
+        let mut options = DeadzoneOptions::new();
+        options.num_simulations = 0;
+        let x_axis = StatAxis::new(DeadzoneStat::DiceStat, true, 8, 10);
+        let y_axis = StatAxis::new(DeadzoneStat::Armor, false, 0, 0);
+        let model = DeadzoneModel::new();
+        assert_eq!(
+            deadzone_calc_heat_map(&model, &model, &options, &x_axis, &y_axis).err(),
+            Some(CalcError::InvalidStat)
+        );
+        let backwards_axis = StatAxis::new(DeadzoneStat::Armor, false, 2, 0);
+        assert_eq!(
+            deadzone_calc_heat_map(&model, &model, &options, &backwards_axis, &y_axis).err(),
+            Some(CalcError::InvalidStat)
+        );
+    }
+
+    // i32::MAX values along numDice are all in range, but far too many cells
+    #[test]
+    fn oversized_grid_is_rejected_before_any_cell() {
+        let options = DeadzoneOptions::new();
+        let x_axis = StatAxis::new(DeadzoneStat::NumDice, true, 0, i32::MAX);
+        let y_axis = StatAxis::new(DeadzoneStat::Armor, false, 0, 0);
+        let model = DeadzoneModel::new();
+        assert_eq!(
+            deadzone_calc_heat_map(&model, &model, &options, &x_axis, &y_axis).err(),
+            Some(CalcError::BudgetExceeded)
+        );
+    }
+}
@@ -0,0 +1,131 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use num::traits::NumAssignRef;
+use wasm_bindgen::prelude::*;
+
+use super::roll_params::{RollParams, PIP_HI};
+use super::simulator::deadzone_clear_success_cache;
+use crate::common::add_to_map_value;
+
+// both hooks only apply to the attacker's and defender's own dice; shield and cover dice always
+// follow the normal rules
+thread_local! {
+    // (pip, diceStat) => bool, whether a single rolled pip counts as a success
+    static DIE_SUCCESS_HOOK: RefCell<Option<js_sys::Function>> = const { RefCell::new(None) };
+    // (numSuccesses, numDice) => number, adjusts the total successes of a whole roll
+    static SUCCESS_COUNT_HOOK: RefCell<Option<js_sys::Function>> = const { RefCell::new(None) };
+}
+
+// the hook decides each pip of every die the models roll, rerolls and explosions included, except
+// for models with face success values; pass undefined to go back to the normal rules.
+// cached success distributions were made under the old rules, so they get cleared
+#[wasm_bindgen(js_name = "deadzoneSetDieSuccessHook")]
+pub fn deadzone_set_die_success_hook(hook: Option<js_sys::Function>) {
+    DIE_SUCCESS_HOOK.with(|cell| *cell.borrow_mut() = hook);
+    deadzone_clear_success_cache();
+}
+
+// the hook gets each roll's total successes, after rerolls and before maxSuccesses caps them, and
+// returns the total to use instead; negative totals become 0. undefined and the cache work the same
+#[wasm_bindgen(js_name = "deadzoneSetSuccessCountHook")]
+pub fn deadzone_set_success_count_hook(hook: Option<js_sys::Function>) {
+    SUCCESS_COUNT_HOOK.with(|cell| *cell.borrow_mut() = hook);
+    deadzone_clear_success_cache();
+}
+
+// success_value_by_pip[pip] is how many successes that pip scores, with 0 being a failure;
+// index 0 is unused. the model's face success values take priority over the die success hook
+pub fn pip_success_values(roll_params: &RollParams) -> [i32; (PIP_HI + 1) as usize] {
+    let mut success_value_by_pip = [0; (PIP_HI + 1) as usize];
+    if let Some(face_success_values) = roll_params.face_success_values {
+        success_value_by_pip[1..].copy_from_slice(&face_success_values);
+        return success_value_by_pip;
+    }
+
+    let dice_stat = roll_params.dice_stat;
+    DIE_SUCCESS_HOOK.with(|cell| {
+        let hook = cell.borrow();
+        for pip in 1..=PIP_HI {
+            let is_success = match hook.as_ref().filter(|_| roll_params.uses_house_rule_hooks) {
+                None => pip >= dice_stat,
+                Some(hook) => hook
+                    .call2(
+                        &JsValue::NULL,
+                        &JsValue::from(pip),
+                        &JsValue::from(dice_stat),
+                    )
+                    .ok()
+                    .and_then(|result| result.as_bool())
+                    .unwrap_or(pip >= dice_stat),
+            };
+            success_value_by_pip[pip as usize] = is_success as i32;
+        }
+    });
+    success_value_by_pip
+}
+
+// remaps each success count through the count hook, merging counts that end up the same, and then
+// caps them at the roll's max successes; gets called once per distinct count rather than once per roll
+pub fn adjust_success_counts<T: Copy + NumAssignRef>(
+    success_counts: HashMap<i32, T>,
+    roll_params: &RollParams,
+) -> HashMap<i32, T> {
+    let hooked_counts = SUCCESS_COUNT_HOOK.with(|cell| {
+        match cell
+            .borrow()
+            .as_ref()
+            .filter(|_| roll_params.uses_house_rule_hooks)
+        {
+            None => success_counts,
+            Some(hook) => {
+                let mut adjusted_counts = HashMap::<i32, T>::new();
+                for (num_successes, count) in success_counts.iter() {
+                    let adjusted_num_successes = hook
+                        .call2(
+                            &JsValue::NULL,
+                            &JsValue::from(*num_successes),
+                            &JsValue::from(roll_params.num_dice),
+                        )
+                        .ok()
+                        .and_then(|result| result.as_f64())
+                        .map_or(*num_successes, |result| std::cmp::max(0, result as i32));
+                    add_to_map_value(&mut adjusted_counts, &adjusted_num_successes, *count);
+                }
+                adjusted_counts
+            }
+        }
+    });
+    if hooked_counts
+        .keys()
+        .all(|num_successes| *num_successes <= roll_params.max_successes)
+    {
+        return hooked_counts;
+    }
+    let mut capped_counts = HashMap::<i32, T>::new();
+    for (num_successes, count) in hooked_counts.iter() {
+        add_to_map_value(
+            &mut capped_counts,
+            &std::cmp::min(*num_successes, roll_params.max_successes),
+            *count,
+        );
+    }
+    capped_counts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deadzone::deadzone_model::DeadzoneModel;
+    use crate::deadzone::deadzone_options::DeadzoneOptions;
+
+    #[test]
+    fn hooks_only_apply_to_the_models_own_dice() {
+        let model = DeadzoneModel::new();
+        let options = DeadzoneOptions::new();
+        assert!(RollParams::new(&model, true, &options).uses_house_rule_hooks);
+        assert!(RollParams::new(&model, false, &options).uses_house_rule_hooks);
+        assert!(!RollParams::for_shields(&model).uses_house_rule_hooks);
+        assert!(!RollParams::for_cover(&options).uses_house_rule_hooks);
+    }
+}
@@ -0,0 +1,123 @@
+use std::collections::HashMap;
+
+use super::exact::calc_exact_success_probs;
+use super::roll_params::RollParams;
+
+// replaces each tail of a simulated success distribution with the exact engine's probs;
+// a tail is the counts whose exact cumulative prob from that end is within tail_mass.
+// the simulated bulk is rescaled to fill whatever mass the exact tails don't
+pub fn stitch_exact_tails(
+    simulated_probs: &HashMap<i32, f64>,
+    roll_params: &RollParams,
+    tail_mass: f64,
+) -> HashMap<i32, f64> {
+    let mut exact_roll_params = *roll_params;
+    exact_roll_params.num_simulations = 0;
+    let exact_probs = calc_exact_success_probs(&exact_roll_params);
+
+    let mut counts: Vec<i32> = exact_probs.keys().copied().collect();
+    counts.sort();
+    let lower_tail = tail_counts(counts.iter(), &exact_probs, tail_mass);
+    let upper_tail = tail_counts(counts.iter().rev(), &exact_probs, tail_mass);
+    let is_tail = |num_successes: &i32| {
+        lower_tail.contains(num_successes) || upper_tail.contains(num_successes)
+    };
+
+    let exact_tail_prob: f64 = exact_probs
+        .iter()
+        .filter(|(num_successes, _)| is_tail(num_successes))
+        .map(|(_, prob)| prob)
+        .sum();
+    let simulated_bulk_prob: f64 = simulated_probs
+        .iter()
+        .filter(|(num_successes, _)| !is_tail(num_successes))
+        .map(|(_, prob)| prob)
+        .sum();
+    if simulated_bulk_prob <= 0.0 {
+        // simulation never landed in the bulk, so it has nothing to add
+        return exact_probs;
+    }
+    let bulk_scale = (1.0 - exact_tail_prob) / simulated_bulk_prob;
+
+    let mut stitched_probs: HashMap<i32, f64> = simulated_probs
+        .iter()
+        .filter(|(num_successes, _)| !is_tail(num_successes))
+        .map(|(num_successes, prob)| (*num_successes, prob * bulk_scale))
+        .collect();
+    for (num_successes, prob) in exact_probs.iter() {
+        if is_tail(num_successes) {
+            stitched_probs.insert(*num_successes, *prob);
+        }
+    }
+    stitched_probs
+}
+
+// counts, walked from one end, until their cumulative prob would exceed tail_mass
+fn tail_counts<'a>(
+    counts_from_end: impl Iterator<Item = &'a i32>,
+    probs: &HashMap<i32, f64>,
+    tail_mass: f64,
+) -> Vec<i32> {
+    let mut cumulative_prob = 0.0;
+    let mut tail = vec![];
+    for num_successes in counts_from_end {
+        cumulative_prob += probs[num_successes];
+        if cumulative_prob > tail_mass {
+            break;
+        }
+        tail.push(*num_successes);
+    }
+    tail
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use super::*;
+    use crate::deadzone::deadzone_model::DeadzoneModel;
+    use crate::deadzone::deadzone_options::DeadzoneOptions;
+    use crate::deadzone::roll_params::{PIP_HI, PIP_LO};
+    use crate::deadzone::simulator::{roll_success_counts, success_counts_to_probs};
+
+    const TAIL_MASS: f64 = 0.05;
+
+    // too few simulations to see most of the tails, which come back exact anyway
+    #[test]
+    fn stitched_tails_match_the_exact_engine() {
+        let mut model = DeadzoneModel::new();
+        model.num_dice = 6;
+        let mut options = DeadzoneOptions::new();
+        options.num_simulations = 2_000;
+        let roll_params = RollParams::new(&model, true, &options);
+        let mut rng = StdRng::seed_from_u64(crate::self_test::SELF_TEST_SEED);
+        let die_distribution = rand::distributions::Uniform::new(PIP_LO, PIP_HI + 1);
+        let simulated_probs = success_counts_to_probs(
+            &roll_success_counts(&die_distribution, &mut rng, &roll_params),
+            options.num_simulations,
+        );
+
+        let stitched_probs = stitch_exact_tails(&simulated_probs, &roll_params, TAIL_MASS);
+        let mut exact_roll_params = roll_params;
+        exact_roll_params.num_simulations = 0;
+        let exact_probs = calc_exact_success_probs(&exact_roll_params);
+
+        assert!((stitched_probs.values().sum::<f64>() - 1.0).abs() < 1e-9);
+        let mut counts: Vec<i32> = exact_probs.keys().copied().collect();
+        counts.sort();
+        let lower_tail = tail_counts(counts.iter(), &exact_probs, TAIL_MASS);
+        let upper_tail = tail_counts(counts.iter().rev(), &exact_probs, TAIL_MASS);
+        assert!(!lower_tail.is_empty() && !upper_tail.is_empty());
+        for num_successes in lower_tail.iter().chain(upper_tail.iter()) {
+            assert_eq!(stitched_probs[num_successes], exact_probs[num_successes]);
+        }
+        for (num_successes, exact_prob) in exact_probs.iter() {
+            let stitched_prob = stitched_probs.get(num_successes).unwrap_or(&0.0);
+            assert!(
+                (stitched_prob - exact_prob).abs() < 0.03,
+                "{num_successes} successes: exact {exact_prob}, stitched {stitched_prob}"
+            );
+        }
+    }
+}
@@ -0,0 +1,204 @@
+use std::collections::HashMap;
+
+use rand::prelude::*;
+
+use super::house_rules::{adjust_success_counts, pip_success_values};
+use super::roll_params::{RollParams, PIP_HI};
+use crate::common::add_to_map_value;
+
+// pips split by whether they're a success, so a tilted roll can pick the group first
+// and then a pip within it just like an untilted roll would
+struct TiltedDie {
+    success_value_by_pip: [i32; (PIP_HI + 1) as usize],
+    success_pips: Vec<i32>,
+    failure_pips: Vec<i32>,
+    success_prob: f64,
+    tilted_success_prob: f64,
+}
+
+impl TiltedDie {
+    fn new(roll_params: &RollParams) -> Self {
+        let success_value_by_pip = pip_success_values(roll_params);
+        let (success_pips, failure_pips): (Vec<i32>, Vec<i32>) =
+            (1..=PIP_HI).partition(|pip| success_value_by_pip[*pip as usize] > 0);
+        let success_prob = success_pips.len() as f64 / PIP_HI as f64;
+        // an all-success or all-failure die has nothing to tilt
+        let tilted_success_prob = if success_pips.is_empty() || failure_pips.is_empty() {
+            success_prob
+        } else {
+            f64::from_bits(roll_params.importance_sampling_success_prob_bits).clamp(0.01, 0.99)
+        };
+        TiltedDie {
+            success_value_by_pip,
+            success_pips,
+            failure_pips,
+            success_prob,
+            tilted_success_prob,
+        }
+    }
+
+    // returns the pip, whether it's a success, and its likelihood ratio (true prob over sampled prob)
+    fn roll<R: Rng>(&self, rng: &mut R) -> (i32, bool, f64) {
+        if rng.gen::<f64>() < self.tilted_success_prob {
+            let pip = *self.success_pips.choose(rng).unwrap();
+            (pip, true, self.success_prob / self.tilted_success_prob)
+        } else {
+            let pip = *self.failure_pips.choose(rng).unwrap();
+            (
+                pip,
+                false,
+                (1.0 - self.success_prob) / (1.0 - self.tilted_success_prob),
+            )
+        }
+    }
+}
+
+// like the simulator's plain rolling, but each die succeeds with the tilted prob instead,
+// and each sample is weighted by its likelihood ratio so the estimate stays unbiased;
+// a tilted prob above the die's real success prob oversamples high-success rolls,
+// so the rare tail (one-shotting a leader) gets many more samples; only the attacker's roll params
+// carry a tilt
+pub fn roll_importance_sampled_success_probs<R: Rng>(
+    rng: &mut R,
+    roll_params: &RollParams,
+) -> HashMap<i32, f64> {
+    let tilted_die = TiltedDie::new(roll_params);
+    let mut weighted_counts = HashMap::<i32, f64>::new();
+    for _ in 0..roll_params.num_simulations {
+        let (num_successes, weight) = roll_weighted_multi(
+            &tilted_die,
+            rng,
+            roll_params.num_dice,
+            roll_params.num_rerolls,
+            roll_params.exploding_dice_max_levels,
+        );
+        add_to_map_value(&mut weighted_counts, &num_successes, weight);
+    }
+    let success_probs = weighted_counts
+        .iter()
+        .map(|(num_successes, weight_sum)| {
+            (
+                *num_successes,
+                weight_sum / roll_params.num_simulations as f64,
+            )
+        })
+        .collect();
+    adjust_success_counts(success_probs, roll_params)
+}
+
+fn roll_weighted_multi<R: Rng>(
+    tilted_die: &TiltedDie,
+    rng: &mut R,
+    num_dice: i32,
+    num_rerolls: i32,
+    exploding_dice_max_levels: i32,
+) -> (i32, f64) {
+    let mut num_successes = 0;
+    let mut num_failures = 0;
+    let mut weight = 1.0;
+
+    for _ in 0..num_dice {
+        let mut num_rolls_of_die = 0;
+        loop {
+            let (pip, is_success, likelihood_ratio) = tilted_die.roll(rng);
+            weight *= likelihood_ratio;
+            num_rolls_of_die += 1;
+            if is_success {
+                num_successes += tilted_die.success_value_by_pip[pip as usize];
+            } else {
+                num_failures += 1;
+            }
+            if pip != PIP_HI || num_rolls_of_die > exploding_dice_max_levels {
+                break;
+            }
+        }
+    }
+
+    if num_rerolls > 0 && num_failures > 0 {
+        let (num_rerolled_successes, reroll_weight) = roll_weighted_multi(
+            tilted_die,
+            rng,
+            std::cmp::min(num_rerolls, num_failures),
+            0,
+            exploding_dice_max_levels,
+        );
+        num_successes += num_rerolled_successes;
+        weight *= reroll_weight;
+    }
+    (num_successes, weight)
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use super::*;
+    use crate::deadzone::deadzone_model::DeadzoneModel;
+    use crate::deadzone::deadzone_options::DeadzoneOptions;
+    use crate::deadzone::roll_params::PIP_LO;
+    use crate::deadzone::simulator::{roll_success_counts, success_counts_to_probs};
+
+    const NUM_SIMULATIONS: i32 = 1_000;
+    const NUM_REPLICATES: usize = 50;
+
+    fn mean_and_variance(estimates: &[f64]) -> (f64, f64) {
+        let mean = estimates.iter().sum::<f64>() / estimates.len() as f64;
+        let variance = estimates
+            .iter()
+            .map(|est| (est - mean).powi(2))
+            .sum::<f64>()
+            / (estimates.len() - 1) as f64;
+        (mean, variance)
+    }
+
+    #[test]
+    fn only_the_attacker_is_tilted() {
+        let model = DeadzoneModel::new();
+        let mut options = DeadzoneOptions::new();
+        options.importance_sampling_success_prob = 0.8;
+        let atk_roll_params = RollParams::new(&model, true, &options);
+        let def_roll_params = RollParams::new(&model, false, &options);
+        assert_eq!(
+            f64::from_bits(atk_roll_params.importance_sampling_success_prob_bits),
+            0.8
+        );
+        assert_eq!(def_roll_params.importance_sampling_success_prob_bits, 0);
+    }
+
+    // all 8 of 8 dice succeeding is a 1/256 tail; tilted rolls land there most of the time, so
+    // repeated estimates of it spread far less than plain sampling's do
+    #[test]
+    fn tilting_reduces_the_variance_of_a_rare_tail() {
+        let mut model = DeadzoneModel::new();
+        model.num_dice = 8;
+        let mut options = DeadzoneOptions::new();
+        options.num_simulations = NUM_SIMULATIONS;
+        options.exploding_dice_max_levels = 0;
+        options.importance_sampling_success_prob = 0.9;
+        let roll_params = RollParams::new(&model, true, &options);
+        let mut rng = StdRng::seed_from_u64(crate::self_test::SELF_TEST_SEED);
+        let die_distribution = rand::distributions::Uniform::new(PIP_LO, PIP_HI + 1);
+
+        let mut tilted_estimates = vec![];
+        let mut plain_estimates = vec![];
+        for _ in 0..NUM_REPLICATES {
+            let tilted_probs = roll_importance_sampled_success_probs(&mut rng, &roll_params);
+            tilted_estimates.push(*tilted_probs.get(&8).unwrap_or(&0.0));
+            let plain_probs = success_counts_to_probs(
+                &roll_success_counts(&die_distribution, &mut rng, &roll_params),
+                NUM_SIMULATIONS,
+            );
+            plain_estimates.push(*plain_probs.get(&8).unwrap_or(&0.0));
+        }
+
+        let tail_prob = 1.0 / 256.0;
+        let (tilted_mean, tilted_variance) = mean_and_variance(&tilted_estimates);
+        let (_, plain_variance) = mean_and_variance(&plain_estimates);
+        assert!((tilted_mean - tail_prob).abs() < 0.1 * tail_prob);
+        assert!(
+            tilted_variance < plain_variance / 10.0,
+            "tilted variance {tilted_variance}, plain variance {plain_variance}"
+        );
+    }
+}
@@ -0,0 +1,112 @@
+use std::collections::HashMap;
+
+use wasm_bindgen::prelude::*;
+
+use super::deadzone_model::{DeadzoneModel, DeadzoneStat};
+use super::deadzone_options::DeadzoneOptions;
+use super::simulator::{
+    calc_multi_round_dmg_probs, calc_success_probs, calc_success_probs_pair,
+    combine_opposed_successes, tidy_output_probs,
+};
+use super::validation::validate_inputs;
+use crate::common::calc_error::CalcError;
+use crate::common::ts_types::ToJsMap;
+
+// holds on to both models' success distributions between calls, so changing a stat that's only
+// used after the roll (armor, AP, toxic, ...) just re-resolves the opposed roll instead of
+// rolling again; that also keeps simulated results from jumping around with sampling noise
+#[wasm_bindgen]
+pub struct DeadzoneIncrementalCalc {
+    attacker: DeadzoneModel,
+    defender: DeadzoneModel,
+    options: DeadzoneOptions,
+    atk_success_probs: HashMap<i32, f64>,
+    def_success_probs: HashMap<i32, f64>,
+}
+
+#[wasm_bindgen]
+impl DeadzoneIncrementalCalc {
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        attacker: &DeadzoneModel,
+        defender: &DeadzoneModel,
+        options: &DeadzoneOptions,
+    ) -> Result<DeadzoneIncrementalCalc, CalcError> {
+        validate_inputs(attacker, defender, options)?;
+        let (atk_success_probs, def_success_probs) =
+            calc_success_probs_pair(attacker, defender, options);
+        Ok(DeadzoneIncrementalCalc {
+            attacker: *attacker,
+            defender: *defender,
+            options: *options,
+            atk_success_probs,
+            def_success_probs,
+        })
+    }
+
+    // changes one stat of one model and returns the new dmg probs; an invalid value leaves
+    // everything as it was
+    #[wasm_bindgen(js_name = setStat)]
+    pub fn set_stat(
+        &mut self,
+        is_attacker: bool,
+        stat: DeadzoneStat,
+        val: i32,
+    ) -> Result<js_sys::Map, CalcError> {
+        let mut attacker = self.attacker;
+        let mut defender = self.defender;
+        if is_attacker {
+            attacker.set_stat(stat, val);
+        } else {
+            defender.set_stat(stat, val);
+        }
+        validate_inputs(&attacker, &defender, &self.options)?;
+
+        self.attacker = attacker;
+        self.defender = defender;
+        if affects_success_probs(stat) {
+            if is_attacker {
+                self.atk_success_probs = calc_success_probs(&attacker, true, &self.options);
+            } else {
+                self.def_success_probs = calc_success_probs(&defender, false, &self.options);
+            }
+        }
+        Ok(self.dmg_probs())
+    }
+
+    #[wasm_bindgen(js_name = dmgProbs)]
+    pub fn dmg_probs(&self) -> js_sys::Map {
+        tidy_output_probs(&self.calc_dmg_probs(), &self.options).to_js_map()
+    }
+}
+
+impl DeadzoneIncrementalCalc {
+    fn calc_dmg_probs(&self) -> HashMap<i32, f64> {
+        let single_round_dmg_probs = combine_opposed_successes(
+            &self.attacker,
+            &self.defender,
+            &self.options,
+            &self.atk_success_probs,
+            &self.def_success_probs,
+        );
+        calc_multi_round_dmg_probs(
+            &single_round_dmg_probs,
+            &self.attacker,
+            &self.defender,
+            &self.options,
+        )
+    }
+}
+
+// stats that go into RollParams, so changing them needs the model's dice rolled again;
+// shield dice are rolled exactly and cached on their own, so they're cheap to redo
+fn affects_success_probs(stat: DeadzoneStat) -> bool {
+    matches!(
+        stat,
+        DeadzoneStat::NumDice
+            | DeadzoneStat::DiceStat
+            | DeadzoneStat::NumRerolls
+            | DeadzoneStat::NumDiceKept
+            | DeadzoneStat::MaxSuccesses
+    )
+}
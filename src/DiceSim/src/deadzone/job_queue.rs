@@ -0,0 +1,91 @@
+use std::collections::{HashMap, VecDeque};
+
+use wasm_bindgen::prelude::*;
+
+use super::deadzone_model::DeadzoneModel;
+use super::deadzone_options::DeadzoneOptions;
+use super::simulator::{calc_dmg_probs, tidy_output_probs};
+use super::validation::validate_inputs;
+use crate::common::calc_error::CalcError;
+use crate::common::now_ms;
+use crate::common::ts_types::ToJsMap;
+
+struct Job {
+    job_id: u32,
+    attacker: DeadzoneModel,
+    defender: DeadzoneModel,
+    options: DeadzoneOptions,
+}
+
+// many dmg calcs, like the points of a sweep, run a few at a time from the UI's animation frames
+// instead of all at once; jobs run in the order they were enqueued, and each finished job's
+// result waits here until it's taken
+#[wasm_bindgen]
+#[derive(Default)]
+pub struct DeadzoneJobQueue {
+    pending_jobs: VecDeque<Job>,
+    finished_jobs: HashMap<u32, Result<HashMap<i32, f64>, CalcError>>,
+    next_job_id: u32,
+}
+
+#[wasm_bindgen]
+impl DeadzoneJobQueue {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> DeadzoneJobQueue {
+        DeadzoneJobQueue::default()
+    }
+
+    // the returned id is for taking the result; invalid inputs aren't caught until the job runs,
+    // and then its result is the error
+    pub fn enqueue(
+        &mut self,
+        attacker: &DeadzoneModel,
+        defender: &DeadzoneModel,
+        options: &DeadzoneOptions,
+    ) -> u32 {
+        let job_id = self.next_job_id;
+        self.next_job_id = self.next_job_id.wrapping_add(1);
+        self.pending_jobs.push_back(Job {
+            job_id,
+            attacker: *attacker,
+            defender: *defender,
+            options: *options,
+        });
+        job_id
+    }
+
+    // runs pending jobs until budget_ms has passed, always finishing at least one so a tiny budget
+    // still makes progress; a job isn't interrupted, so one slow job can overrun the budget.
+    // returns how many jobs are still pending
+    pub fn poll(&mut self, budget_ms: f64) -> u32 {
+        let start_ms = now_ms();
+        while let Some(job) = self.pending_jobs.pop_front() {
+            let result = validate_inputs(&job.attacker, &job.defender, &job.options).map(|_| {
+                tidy_output_probs(
+                    &calc_dmg_probs(&job.attacker, &job.defender, &job.options),
+                    &job.options,
+                )
+            });
+            self.finished_jobs.insert(job.job_id, result);
+            if now_ms() - start_ms >= budget_ms {
+                break;
+            }
+        }
+        self.num_pending()
+    }
+
+    // undefined while the job is still pending (or its result was already taken); taking a result
+    // frees it, so each result can only be taken once
+    #[wasm_bindgen(js_name = takeResult)]
+    pub fn take_result(&mut self, job_id: u32) -> Result<Option<js_sys::Map>, CalcError> {
+        self.finished_jobs
+            .remove(&job_id)
+            .map(|result| result.map(|dmg_probs| dmg_probs.to_js_map()))
+            .transpose()
+    }
+
+    #[wasm_bindgen(getter, js_name = numPending)]
+    pub fn num_pending(&self) -> u32 {
+        self.pending_jobs.len() as u32
+    }
+}
@@ -0,0 +1,61 @@
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
+use wasm_bindgen::prelude::*;
+
+use super::deadzone_model::DeadzoneModelList;
+use super::deadzone_options::DeadzoneOptions;
+use super::simulator::calc_dmg_probs;
+use super::validation::validate_inputs;
+use crate::common::calc_error::CalcError;
+use crate::common::ts_types::{result_schema_v1, RESULT_SCHEMA_VERSION};
+use crate::common::{expected_value, prob_at_least};
+
+#[derive(Tsify, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ThreatCell {
+    pub expected_dmg: f64, // negative damage (to attacker) reduces it, like deadzoneCalcDmgByRound
+    pub kill_prob: f64,
+}
+
+// cells[i][j] is attacker i against defender j
+#[derive(Tsify, Serialize, Deserialize)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+#[serde(rename_all = "camelCase")]
+pub struct ThreatMatrix {
+    #[serde(default = "result_schema_v1")]
+    pub result_schema_version: u32,
+    pub cells: Vec<Vec<ThreatCell>>,
+}
+
+// roster-wide threat table; each model's success distribution is cached by its roll params,
+// so every attacker and defender gets rolled once rather than once per pairing
+// (as long as the roster fits in the cache)
+#[wasm_bindgen(js_name = "deadzoneCalcThreatMatrix")]
+pub fn deadzone_calc_threat_matrix(
+    attackers: &DeadzoneModelList,
+    defenders: &DeadzoneModelList,
+    options: &DeadzoneOptions,
+) -> Result<ThreatMatrix, CalcError> {
+    let cells: Vec<Vec<ThreatCell>> = attackers
+        .models()
+        .iter()
+        .map(|attacker| {
+            defenders
+                .models()
+                .iter()
+                .map(|defender| {
+                    validate_inputs(attacker, defender, options)?;
+                    let dmg_probs = calc_dmg_probs(attacker, defender, options);
+                    Ok(ThreatCell {
+                        expected_dmg: expected_value(&dmg_probs),
+                        kill_prob: prob_at_least(&dmg_probs, defender.hp),
+                    })
+                })
+                .collect()
+        })
+        .collect::<Result<_, CalcError>>()?;
+    Ok(ThreatMatrix {
+        result_schema_version: RESULT_SCHEMA_VERSION,
+        cells,
+    })
+}
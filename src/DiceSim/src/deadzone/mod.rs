@@ -1,3 +1,33 @@
+mod action_sequence;
+mod allocation;
+mod analysis;
+mod convergence;
 mod deadzone_model;
 mod deadzone_options;
+mod duel;
+mod exact;
+mod fight;
+mod heat_map;
+mod house_rules;
+mod hybrid;
+mod importance_sampling;
+mod incremental;
+mod job_queue;
+mod matrix;
+mod outcomes;
+mod persistence;
+mod replay;
+mod roll_params;
+mod sample_stream;
+mod scenario_events;
+mod self_test;
 mod simulator;
+#[cfg(feature = "test-vectors")]
+mod test_vectors;
+mod uncertainty;
+mod validation;
+mod weapon_presets;
+
+pub(crate) use self_test::self_test_checks;
+#[cfg(feature = "test-vectors")]
+pub(crate) use test_vectors::test_vectors;
@@ -0,0 +1,155 @@
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
+use wasm_bindgen::prelude::*;
+
+use super::deadzone_model::DeadzoneModel;
+use super::deadzone_options::DeadzoneOptions;
+use super::simulator::{calc_success_probs_pair, for_each_opposed_outcome, ZeroDmgCause};
+use super::validation::validate_inputs;
+use crate::common::calc_error::CalcError;
+use crate::common::ts_types::{result_schema_v1, RESULT_SCHEMA_VERSION};
+
+// how a single attack against the defender turned out; the probs sum to 1
+#[derive(Tsify, Serialize, Deserialize)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+#[serde(rename_all = "camelCase")]
+pub struct AttackOutcomeBreakdown {
+    #[serde(default = "result_schema_v1")]
+    pub result_schema_version: u32,
+    pub instant_kill_prob: f64, // won by the attacker's instantKillMargin or more
+    pub wound_kill_prob: f64,   // dmg reached the defender's hp
+    pub wounded_prob: f64,      // took dmg but survived
+    pub no_dmg_prob: f64,       // includes the attacker taking dmg in a Fight
+    // no_dmg_prob split up by why the defender took no dmg; added in v3, so 0 in older results
+    #[serde(default)]
+    pub tied_roll_prob: f64,
+    #[serde(default)]
+    pub attacker_lost_roll_prob: f64,
+    #[serde(default)]
+    pub shields_absorbed_prob: f64,
+    #[serde(default)]
+    pub armor_absorbed_prob: f64,
+    #[serde(default)]
+    pub dmg_capped_prob: f64, // by the attacker's maxDmg
+}
+
+// one round only, since an instant kill isn't dmg that adds up over rounds
+#[wasm_bindgen(js_name = "deadzoneCalcAttackOutcomes")]
+pub fn deadzone_calc_attack_outcomes(
+    attacker: &DeadzoneModel,
+    defender: &DeadzoneModel,
+    options: &DeadzoneOptions,
+) -> Result<AttackOutcomeBreakdown, CalcError> {
+    validate_inputs(attacker, defender, options)?;
+    let mut breakdown = AttackOutcomeBreakdown {
+        result_schema_version: RESULT_SCHEMA_VERSION,
+        instant_kill_prob: 0.0,
+        wound_kill_prob: 0.0,
+        wounded_prob: 0.0,
+        no_dmg_prob: 0.0,
+        tied_roll_prob: 0.0,
+        attacker_lost_roll_prob: 0.0,
+        shields_absorbed_prob: 0.0,
+        armor_absorbed_prob: 0.0,
+        dmg_capped_prob: 0.0,
+    };
+    let (atk_success_probs, def_success_probs) =
+        calc_success_probs_pair(attacker, defender, options);
+
+    for_each_opposed_outcome(
+        attacker,
+        defender,
+        options,
+        &atk_success_probs,
+        &def_success_probs,
+        |outcome, prob| {
+            let outcome_prob = if outcome.is_instant_kill && outcome.dmg > 0 {
+                &mut breakdown.instant_kill_prob
+            } else if outcome.dmg >= defender.hp {
+                &mut breakdown.wound_kill_prob
+            } else if outcome.dmg > 0 {
+                &mut breakdown.wounded_prob
+            } else {
+                &mut breakdown.no_dmg_prob
+            };
+            *outcome_prob += prob;
+
+            let cause_prob = match outcome.zero_dmg_cause {
+                Some(ZeroDmgCause::TiedRoll) => &mut breakdown.tied_roll_prob,
+                Some(ZeroDmgCause::AttackerLostRoll) => &mut breakdown.attacker_lost_roll_prob,
+                Some(ZeroDmgCause::ShieldsAbsorbed) => &mut breakdown.shields_absorbed_prob,
+                Some(ZeroDmgCause::ArmorAbsorbed) => &mut breakdown.armor_absorbed_prob,
+                Some(ZeroDmgCause::DmgCapped) => &mut breakdown.dmg_capped_prob,
+                None => return,
+            };
+            *cause_prob += prob;
+        },
+    );
+    Ok(breakdown)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // one die each needing 5+ without explosions, so the margin is +1, 0 or -1 with probs 1/4, 1/2, 1/4
+    fn one_die_model() -> DeadzoneModel {
+        let mut model = DeadzoneModel::new();
+        model.num_dice = 1;
+        model
+    }
+
+    fn exact_options() -> DeadzoneOptions {
+        let mut options = DeadzoneOptions::new();
+        options.num_simulations = 0;
+        options.exploding_dice_max_levels = 0;
+        options
+    }
+
+    fn assert_causes(breakdown: &AttackOutcomeBreakdown, expected_causes: [f64; 5]) {
+        let causes = [
+            ("tied roll", breakdown.tied_roll_prob),
+            ("attacker lost roll", breakdown.attacker_lost_roll_prob),
+            ("shields absorbed", breakdown.shields_absorbed_prob),
+            ("armor absorbed", breakdown.armor_absorbed_prob),
+            ("dmg capped", breakdown.dmg_capped_prob),
+        ];
+        for ((name, prob), expected_prob) in causes.iter().zip(expected_causes.iter()) {
+            assert!(
+                (prob - expected_prob).abs() < 1e-12,
+                "{name}: expected {expected_prob}, got {prob}"
+            );
+        }
+        let total: f64 = causes.iter().map(|(_, prob)| prob).sum();
+        assert!((total - breakdown.no_dmg_prob).abs() < 1e-12);
+    }
+
+    // a won roll is 1 dmg, which the 6+ shield die stops 3/8 of the time and armor 1 stops otherwise;
+    // losing the roll is its own cause in a Fight too, even though the attacker takes dmg then
+    #[test]
+    fn no_dmg_causes_from_shields_and_armor() {
+        let attacker = one_die_model();
+        let mut defender = one_die_model();
+        defender.armor = 1;
+        defender.num_shield_dice = 1;
+        let mut options = exact_options();
+        for attacker_can_be_damaged in [false, true] {
+            options.attacker_can_be_damaged = attacker_can_be_damaged;
+            let breakdown = deadzone_calc_attack_outcomes(&attacker, &defender, &options).unwrap();
+            assert_causes(
+                &breakdown,
+                [0.5, 0.25, 0.25 * 3.0 / 8.0, 0.25 * 5.0 / 8.0, 0.0],
+            );
+        }
+    }
+
+    #[test]
+    fn no_dmg_cause_from_the_dmg_cap() {
+        let mut attacker = one_die_model();
+        attacker.max_dmg = 0;
+        let breakdown =
+            deadzone_calc_attack_outcomes(&attacker, &one_die_model(), &exact_options()).unwrap();
+        assert_causes(&breakdown, [0.5, 0.25, 0.0, 0.0, 0.25]);
+        assert_eq!(breakdown.no_dmg_prob, 1.0);
+    }
+}
@@ -0,0 +1,66 @@
+use wasm_bindgen::prelude::*;
+
+use super::deadzone_model::DeadzoneModel;
+use super::deadzone_options::DeadzoneOptions;
+use super::validation::{validate_model, validate_options};
+use crate::common::calc_error::CalcError;
+
+// json uses the same camelCase field names as the JS classes;
+// missing fields get their defaults, so older saved loadouts keep loading as fields get added.
+// loaded values are validated like any other input, since saved json can be edited by hand
+
+#[wasm_bindgen(js_name = "deadzoneModelToJson")]
+pub fn deadzone_model_to_json(model: &DeadzoneModel) -> String {
+    serde_json::to_string(model).unwrap()
+}
+
+#[wasm_bindgen(js_name = "deadzoneModelFromJson")]
+pub fn deadzone_model_from_json(json: &str) -> Result<DeadzoneModel, CalcError> {
+    let model = serde_json::from_str(json).map_err(|_| CalcError::InvalidJson)?;
+    validate_model(&model)?;
+    Ok(model)
+}
+
+#[wasm_bindgen(js_name = "deadzoneOptionsToJson")]
+pub fn deadzone_options_to_json(options: &DeadzoneOptions) -> String {
+    serde_json::to_string(options).unwrap()
+}
+
+#[wasm_bindgen(js_name = "deadzoneOptionsFromJson")]
+pub fn deadzone_options_from_json(json: &str) -> Result<DeadzoneOptions, CalcError> {
+    let options = serde_json::from_str(json).map_err(|_| CalcError::InvalidJson)?;
+    validate_options(&options)?;
+    Ok(options)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn loaded_json_is_parsed_then_validated() {
+        let mut model = DeadzoneModel::new();
+        model.num_dice = 4;
+        let loaded_model = deadzone_model_from_json(&deadzone_model_to_json(&model)).unwrap();
+        assert_eq!(loaded_model.num_dice, 4);
+        assert_eq!(
+            deadzone_model_from_json(r#"{"numDice": "four"}"#).err(),
+            Some(CalcError::InvalidJson)
+        );
+        assert_eq!(
+            deadzone_model_from_json(r#"{"numDice": -1}"#).err(),
+            Some(CalcError::InvalidStat)
+        );
+
+        let options = DeadzoneOptions::new();
+        assert!(deadzone_options_from_json(&deadzone_options_to_json(&options)).is_ok());
+        assert_eq!(
+            deadzone_options_from_json("{").err(),
+            Some(CalcError::InvalidJson)
+        );
+        assert_eq!(
+            deadzone_options_from_json(r#"{"numRounds": 0}"#).err(),
+            Some(CalcError::InvalidStat)
+        );
+    }
+}
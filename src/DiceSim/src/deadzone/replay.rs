@@ -0,0 +1,146 @@
+use std::collections::HashMap;
+
+use wasm_bindgen::prelude::*;
+
+use super::deadzone_model::DeadzoneModel;
+use super::deadzone_options::DeadzoneOptions;
+use super::roll_params::{RollParams, PIP_HI, PIP_LO};
+use super::simulator::{
+    calc_multi_round_dmg_probs, combine_opposed_successes, roll_observed_success_counts,
+    success_counts_to_probs, tidy_output_probs,
+};
+use super::validation::validate_inputs;
+use crate::common::calc_error::CalcError;
+use crate::common::ts_types::ToJsMap;
+
+// nibbles other than pips 1-8
+const END_OF_ROLL_NIBBLE: u8 = 0;
+const REROLLS_FOLLOW_NIBBLE: u8 = 9;
+
+// gets told about each pip as the simulator rolls it; () ignores everything,
+// so plain simulation pays nothing for the hooks
+pub trait PipObserver {
+    fn on_pip(&mut self, _pip: i32) {}
+    fn on_rerolls(&mut self) {}
+    fn on_roll_end(&mut self) {}
+}
+
+impl PipObserver for () {}
+
+#[derive(Default)]
+struct NibbleRecorder {
+    packed: Vec<u8>,
+    num_nibbles: usize,
+}
+
+impl NibbleRecorder {
+    // low nibble first
+    fn push(&mut self, nibble: u8) {
+        if self.num_nibbles.is_multiple_of(2) {
+            self.packed.push(nibble);
+        } else {
+            *self.packed.last_mut().unwrap() |= nibble << 4;
+        }
+        self.num_nibbles += 1;
+    }
+}
+
+impl PipObserver for NibbleRecorder {
+    fn on_pip(&mut self, pip: i32) {
+        self.push(pip as u8);
+    }
+
+    fn on_rerolls(&mut self) {
+        self.push(REROLLS_FOLLOW_NIBBLE);
+    }
+
+    fn on_roll_end(&mut self) {
+        self.push(END_OF_ROLL_NIBBLE);
+    }
+}
+
+// every pip behind a simulated dmg distribution, two per byte with the low nibble first.
+// each roll is its pips in order (an 8 is followed by its explosion's pip), then 9 if rerolls
+// follow and the rerolled pips, then 0 to end the roll. all the attacker's rolls come first,
+// and the defender's start at defenderStartNibble
+#[wasm_bindgen]
+pub struct RollReplay {
+    dmg_probs: HashMap<i32, f64>,
+    packed_pips: Vec<u8>,
+    num_nibbles: usize,
+    defender_start_nibble: usize,
+}
+
+#[wasm_bindgen]
+impl RollReplay {
+    #[wasm_bindgen(js_name = dmgProbs)]
+    pub fn dmg_probs(&self) -> js_sys::Map {
+        self.dmg_probs.to_js_map()
+    }
+
+    #[wasm_bindgen(getter, js_name = packedPips)]
+    pub fn packed_pips(&self) -> Vec<u8> {
+        self.packed_pips.clone()
+    }
+
+    // the last byte's high nibble is padding when this is odd
+    #[wasm_bindgen(getter, js_name = numNibbles)]
+    pub fn num_nibbles(&self) -> usize {
+        self.num_nibbles
+    }
+
+    #[wasm_bindgen(getter, js_name = defenderStartNibble)]
+    pub fn defender_start_nibble(&self) -> usize {
+        self.defender_start_nibble
+    }
+}
+
+// like deadzoneCalcDmgProbs, but always rolls fresh instead of using the cache, recording every pip;
+// only plain simulation has individual rolls to record
+#[wasm_bindgen(js_name = "deadzoneCalcDmgProbsWithReplay")]
+pub fn deadzone_calc_dmg_probs_with_replay(
+    attacker: &DeadzoneModel,
+    defender: &DeadzoneModel,
+    options: &DeadzoneOptions,
+) -> Result<RollReplay, CalcError> {
+    validate_inputs(attacker, defender, options)?;
+    if options.num_simulations == 0
+        || options.importance_sampling_success_prob != 0.0
+        || options.exact_tail_mass != 0.0
+    {
+        return Err(CalcError::Unsupported);
+    }
+
+    let mut rng = rand::thread_rng();
+    let die_distribution = rand::distributions::Uniform::new(PIP_LO, PIP_HI + 1);
+    let mut recorder = NibbleRecorder::default();
+    let atk_success_counts = roll_observed_success_counts(
+        &die_distribution,
+        &mut rng,
+        &RollParams::new(attacker, true, options),
+        &mut recorder,
+    );
+    let defender_start_nibble = recorder.num_nibbles;
+    let def_success_counts = roll_observed_success_counts(
+        &die_distribution,
+        &mut rng,
+        &RollParams::new(defender, false, options),
+        &mut recorder,
+    );
+
+    let mut dmg_probs = combine_opposed_successes(
+        attacker,
+        defender,
+        options,
+        &success_counts_to_probs(&atk_success_counts, options.num_simulations),
+        &success_counts_to_probs(&def_success_counts, options.num_simulations),
+    );
+    dmg_probs = calc_multi_round_dmg_probs(&dmg_probs, attacker, defender, options);
+
+    Ok(RollReplay {
+        dmg_probs: tidy_output_probs(&dmg_probs, options),
+        packed_pips: recorder.packed,
+        num_nibbles: recorder.num_nibbles,
+        defender_start_nibble,
+    })
+}
@@ -0,0 +1,143 @@
+use super::deadzone_model::{DeadzoneModel, NUM_DIE_FACES};
+use super::deadzone_options::DeadzoneOptions;
+
+// pips of the die every roll uses, shields and cover included
+pub const PIP_LO: i32 = 1;
+pub const PIP_HI: i32 = NUM_DIE_FACES as i32;
+
+// shield dice succeed on 6+, before modifiers
+const SHIELD_DICE_STAT: i32 = 6;
+
+// a pinned (suppressed) model rolls this many fewer dice
+const PINNED_DICE_PENALTY: i32 = 1;
+
+// everything that determines a model's success distribution, so it can key caches
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RollParams {
+    pub num_dice: i32,
+    pub dice_stat: i32,
+    pub face_success_values: Option<[i32; NUM_DIE_FACES]>,
+    pub num_rerolls: i32,
+    pub num_dice_kept: i32,
+    pub max_successes: i32,
+    pub num_forced_rerolls: i32, // of this model's successes, chosen by the opponent
+    pub num_simulations: i32,
+    pub exploding_dice_max_levels: i32,
+    pub importance_sampling_success_prob_bits: u64, // f64 bits, since f64 isn't Hash
+    pub exact_tail_mass_bits: u64,
+    pub uses_house_rule_hooks: bool, // only the models' own dice, not shield or cover dice
+}
+
+impl RollParams {
+    pub fn new(model: &DeadzoneModel, is_attacker: bool, options: &DeadzoneOptions) -> RollParams {
+        RollParams {
+            num_dice: effective_num_dice(model, is_attacker, options),
+            dice_stat: effective_dice_stat(model, is_attacker, options),
+            face_success_values: model.face_success_values,
+            num_rerolls: model.num_rerolls,
+            num_dice_kept: model.num_dice_kept,
+            max_successes: model.max_successes,
+            num_forced_rerolls: if is_attacker {
+                options.attacker_forced_rerolls
+            } else {
+                options.defender_forced_rerolls
+            },
+            num_simulations: options.num_simulations,
+            exploding_dice_max_levels: options.exploding_dice_max_levels,
+            // only the attacker's successes push dmg into the high tail, so the defender's
+            // dice are sampled plainly rather than tilted the same way
+            importance_sampling_success_prob_bits: if is_attacker {
+                options.importance_sampling_success_prob.to_bits()
+            } else {
+                0
+            },
+            exact_tail_mass_bits: options.exact_tail_mass.to_bits(),
+            uses_house_rule_hooks: true,
+        }
+    }
+
+    // shield dice go through the same rolling core as other dice, but never explode,
+    // and are always calculated exactly since there are only ever a few of them
+    pub fn for_shields(model: &DeadzoneModel) -> RollParams {
+        RollParams {
+            num_dice: model.num_shield_dice,
+            dice_stat: SHIELD_DICE_STAT - model.shield_pip_modifier,
+            face_success_values: None,
+            num_rerolls: model.num_shield_rerolls,
+            num_dice_kept: i32::MAX,
+            max_successes: i32::MAX,
+            num_forced_rerolls: 0,
+            num_simulations: 0,
+            exploding_dice_max_levels: 0,
+            importance_sampling_success_prob_bits: 0,
+            exact_tail_mass_bits: 0,
+            uses_house_rule_hooks: false,
+        }
+    }
+
+    // cover dice are their own pool, always calculated exactly like shield dice, but explode like
+    // the defender's other dice
+    pub fn for_cover(options: &DeadzoneOptions) -> RollParams {
+        RollParams {
+            num_dice: options.num_cover_dice,
+            dice_stat: options.cover_dice_stat,
+            face_success_values: None,
+            num_rerolls: 0,
+            num_dice_kept: i32::MAX,
+            max_successes: i32::MAX,
+            num_forced_rerolls: 0,
+            num_simulations: 0,
+            exploding_dice_max_levels: options.exploding_dice_max_levels,
+            importance_sampling_success_prob_bits: 0,
+            exact_tail_mass_bits: 0,
+            uses_house_rule_hooks: false,
+        }
+    }
+
+    // whether dice beyond the kept ones exist
+    pub fn drops_dice(&self) -> bool {
+        self.num_dice_kept < self.num_dice.saturating_add(self.num_rerolls)
+    }
+
+    // whether dice have to be rolled and ranked individually, since some get dropped or the
+    // opponent picks the best ones to be rerolled
+    pub fn ranks_dice(&self) -> bool {
+        self.drops_dice() || self.num_forced_rerolls > 0
+    }
+}
+
+// Aim is a Long Action that gives the attacker +1 die on its Shoot test, so not in a Fight, which is
+// when the attacker can be damaged; a model with no dice of its own has no test to aim
+pub fn aim_bonus_dice(model: &DeadzoneModel, is_attacker: bool, options: &DeadzoneOptions) -> i32 {
+    if is_attacker && options.aimed && !options.attacker_can_be_damaged && model.num_dice > 0 {
+        1
+    } else {
+        0
+    }
+}
+
+// number of dice actually rolled after situational bonuses from the options and being pinned;
+// assists, charging and defensive stances only count in a Fight
+fn effective_num_dice(model: &DeadzoneModel, is_attacker: bool, options: &DeadzoneOptions) -> i32 {
+    let mut num_dice = model.num_dice + aim_bonus_dice(model, is_attacker, options);
+    if options.attacker_can_be_damaged {
+        num_dice += if is_attacker {
+            options.attacker_num_assists + options.charge_bonus_dice
+        } else {
+            options.defender_num_assists + options.defensive_stance_dice
+        };
+    }
+    if model.pinned {
+        num_dice -= PINNED_DICE_PENALTY;
+    }
+    std::cmp::max(0, num_dice)
+}
+
+// a charging attacker's pip bonus in a Fight; the stat can't go below 1+, which already always succeeds
+fn effective_dice_stat(model: &DeadzoneModel, is_attacker: bool, options: &DeadzoneOptions) -> i32 {
+    if is_attacker && options.attacker_can_be_damaged {
+        std::cmp::max(1, model.dice_stat - options.charge_pip_bonus)
+    } else {
+        model.dice_stat
+    }
+}
@@ -0,0 +1,111 @@
+use std::collections::HashMap;
+
+use rand::distributions::Distribution;
+use rand::rngs::ThreadRng;
+use wasm_bindgen::prelude::*;
+
+use super::deadzone_model::DeadzoneModel;
+use super::deadzone_options::DeadzoneOptions;
+use super::roll_params::{RollParams, PIP_HI, PIP_LO};
+use super::simulator::{combine_opposed_successes, roll_num_successes};
+use super::validation::validate_inputs;
+use crate::common::calc_error::CalcError;
+
+// raw simulated dmg, one sample per attack over all the options' rounds, handed out a batch at a time
+// for external tools to run their own estimators on; options.numSimulations is ignored since the
+// caller decides how many samples to take, and samples are always plain rather than importance sampled
+#[wasm_bindgen]
+pub struct DeadzoneSampleStream {
+    attacker: DeadzoneModel,
+    defender: DeadzoneModel,
+    options: DeadzoneOptions,
+    atk_roll_params: RollParams,
+    def_roll_params: RollParams,
+    die_distribution: rand::distributions::Uniform<i32>,
+    rng: ThreadRng,
+    num_samples: i64,
+}
+
+#[wasm_bindgen]
+impl DeadzoneSampleStream {
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        attacker: &DeadzoneModel,
+        defender: &DeadzoneModel,
+        options: &DeadzoneOptions,
+    ) -> Result<DeadzoneSampleStream, CalcError> {
+        validate_inputs(attacker, defender, options)?;
+        Ok(DeadzoneSampleStream {
+            attacker: *attacker,
+            defender: *defender,
+            options: *options,
+            atk_roll_params: RollParams::new(attacker, true, options),
+            def_roll_params: RollParams::new(defender, false, options),
+            die_distribution: rand::distributions::Uniform::new(PIP_LO, PIP_HI + 1),
+            rng: rand::thread_rng(),
+            num_samples: 0,
+        })
+    }
+
+    // signed dmg like deadzoneCalcDmgProbs, so negative dmg was dealt to the attacker
+    #[wasm_bindgen(js_name = nextBatch)]
+    pub fn next_batch(&mut self, batch_size: u32) -> Vec<i32> {
+        let batch: Vec<i32> = (0..batch_size).map(|_| self.next_sample()).collect();
+        self.num_samples += batch.len() as i64;
+        batch
+    }
+
+    // samples handed out so far; f64 since JS numbers can't hold every i64
+    #[wasm_bindgen(getter, js_name = numSamples)]
+    pub fn num_samples(&self) -> f64 {
+        self.num_samples as f64
+    }
+}
+
+impl DeadzoneSampleStream {
+    fn next_sample(&mut self) -> i32 {
+        let mut atk_dmg_taken = 0;
+        let mut def_dmg_taken = 0;
+        for _round_number in 1..=std::cmp::max(1, self.options.num_rounds) {
+            if self.options.stop_when_dead
+                && (atk_dmg_taken >= self.attacker.hp || def_dmg_taken >= self.defender.hp)
+            {
+                break;
+            }
+            let dmg = self.roll_round_dmg();
+            atk_dmg_taken += std::cmp::max(0, -dmg);
+            def_dmg_taken += std::cmp::max(0, dmg);
+        }
+        def_dmg_taken - atk_dmg_taken
+    }
+
+    // the opposed roll is sampled die by die, and then what's left to chance after it, like shield
+    // and cover dice, is sampled from its exact distribution
+    fn roll_round_dmg(&mut self) -> i32 {
+        let atk_successes =
+            roll_num_successes(&self.die_distribution, &mut self.rng, &self.atk_roll_params);
+        let def_successes =
+            roll_num_successes(&self.die_distribution, &mut self.rng, &self.def_roll_params);
+        let dmg_probs = combine_opposed_successes(
+            &self.attacker,
+            &self.defender,
+            &self.options,
+            &HashMap::from([(atk_successes, 1.0)]),
+            &HashMap::from([(def_successes, 1.0)]),
+        );
+        sample_from_probs(&dmg_probs, &mut self.rng)
+    }
+}
+
+fn sample_from_probs(probs: &HashMap<i32, f64>, rng: &mut ThreadRng) -> i32 {
+    let mut remaining_prob = rand::distributions::Uniform::new(0.0, 1.0).sample(rng);
+    let mut last_val = 0;
+    for (val, prob) in probs.iter() {
+        remaining_prob -= prob;
+        last_val = *val;
+        if remaining_prob < 0.0 {
+            break;
+        }
+    }
+    last_val
+}
@@ -0,0 +1,63 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
+use wasm_bindgen::prelude::*;
+
+use super::deadzone_model::NUM_DIE_FACES;
+use crate::common::calc_error::CalcError;
+use crate::common::ts_types::ToJsMap;
+use crate::common::{add_to_map_value, calc_multi_round_damage};
+
+// rolls from min_roll to max_roll on the d8 get this event; effect is whatever the scenario tracks,
+// like victory points or reinforcements, and can be negative
+#[derive(Tsify, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScenarioEvent {
+    pub min_roll: i32,
+    pub max_roll: i32,
+    pub effect: i32,
+}
+
+// every face of the d8 has to be covered by exactly one event
+#[derive(Tsify, Serialize, Deserialize)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct ScenarioEventTable(pub Vec<ScenarioEvent>);
+
+// distribution of the summed effects from rolling on the table num_rolls times, like once per turn
+#[wasm_bindgen(js_name = "deadzoneCalcScenarioEventEffects")]
+pub fn deadzone_calc_scenario_event_effects(
+    table: ScenarioEventTable,
+    num_rolls: i32,
+) -> Result<js_sys::Map, CalcError> {
+    if num_rolls < 1 {
+        return Err(CalcError::InvalidStat);
+    }
+    let single_roll_effect_probs = calc_single_roll_effect_probs(&table)?;
+    Ok(calc_multi_round_damage(&single_roll_effect_probs, num_rolls).to_js_map())
+}
+
+fn calc_single_roll_effect_probs(
+    table: &ScenarioEventTable,
+) -> Result<HashMap<i32, f64>, CalcError> {
+    let mut effect_by_face = [None; NUM_DIE_FACES];
+    for event in table.0.iter() {
+        if event.min_roll < 1 || event.max_roll > NUM_DIE_FACES as i32 {
+            return Err(CalcError::InvalidStat);
+        }
+        for face in event.min_roll..=event.max_roll {
+            let effect = &mut effect_by_face[face as usize - 1];
+            if effect.is_some() {
+                return Err(CalcError::InvalidStat);
+            }
+            *effect = Some(event.effect);
+        }
+    }
+
+    let mut effect_probs = HashMap::<i32, f64>::new();
+    for effect in effect_by_face.iter() {
+        let effect = effect.ok_or(CalcError::InvalidStat)?;
+        add_to_map_value(&mut effect_probs, &effect, 1.0 / NUM_DIE_FACES as f64);
+    }
+    Ok(effect_probs)
+}
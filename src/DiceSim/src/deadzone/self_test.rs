@@ -0,0 +1,120 @@
+use std::collections::HashMap;
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use super::deadzone_model::DeadzoneModel;
+use super::deadzone_options::DeadzoneOptions;
+use super::exact::calc_exact_success_probs;
+use super::roll_params::{RollParams, PIP_HI, PIP_LO};
+use super::simulator::{calc_success_margin_probs, roll_success_counts, success_counts_to_probs};
+use crate::common::binomial_pmf;
+use crate::self_test::{check_probs_match, SelfTestCheck, SELF_TEST_SEED};
+
+// tolerance for exact calcs, which should only be off by float rounding
+const EXACT_TOLERANCE: f64 = 1e-12;
+// with this many simulations, a bucket's standard error is at most about 0.0035
+const NUM_SIMULATIONS: i32 = 20_000;
+const SIMULATED_TOLERANCE: f64 = 0.015;
+
+pub(crate) fn self_test_checks() -> Vec<SelfTestCheck> {
+    vec![
+        check_symmetric_matchup(),
+        check_no_rerolls_is_binomial(),
+        check_rerolling_every_failure(),
+        check_seeded_simulation_matches_exact(),
+    ]
+}
+
+// dice that succeed on 4+ and never explode, so every die is a coin flip at 5/8
+fn plain_roll_params(num_dice: i32, num_rerolls: i32) -> (RollParams, f64) {
+    let mut model = DeadzoneModel::new();
+    model.num_dice = num_dice;
+    model.dice_stat = 4;
+    model.num_rerolls = num_rerolls;
+    let mut options = DeadzoneOptions::new();
+    options.num_simulations = 0;
+    options.exploding_dice_max_levels = 0;
+    (RollParams::new(&model, true, &options), 5.0 / 8.0)
+}
+
+fn binomial_probs(num_trials: i32, prob_success: f64) -> HashMap<i32, f64> {
+    (0..=num_trials)
+        .map(|k| (k, binomial_pmf(num_trials, k, prob_success)))
+        .collect()
+}
+
+// identical models are as likely to win by any margin as to lose by it
+fn check_symmetric_matchup() -> SelfTestCheck {
+    let mut options = DeadzoneOptions::new();
+    options.num_simulations = 0;
+    let model = DeadzoneModel::new();
+    let margin_probs = calc_success_margin_probs(&model, &model, &options);
+    let mirrored_margin_probs = margin_probs
+        .iter()
+        .map(|(margin, prob)| (-margin, *prob))
+        .collect();
+    check_probs_match(
+        "deadzone.symmetricMatchup",
+        &margin_probs,
+        &mirrored_margin_probs,
+        EXACT_TOLERANCE,
+    )
+}
+
+fn check_no_rerolls_is_binomial() -> SelfTestCheck {
+    let (roll_params, prob_success) = plain_roll_params(5, 0);
+    check_probs_match(
+        "deadzone.noRerollsIsBinomial",
+        &binomial_probs(5, prob_success),
+        &calc_exact_success_probs(&roll_params),
+        EXACT_TOLERANCE,
+    )
+}
+
+// with a reroll for every die, each die gets two tries at succeeding
+fn check_rerolling_every_failure() -> SelfTestCheck {
+    let (roll_params, prob_success) = plain_roll_params(4, 4);
+    check_probs_match(
+        "deadzone.rerollingEveryFailure",
+        &binomial_probs(4, 1.0 - (1.0 - prob_success).powi(2)),
+        &calc_exact_success_probs(&roll_params),
+        EXACT_TOLERANCE,
+    )
+}
+
+// explosions and rerolls together, rolled die by die, should land near the exact engine
+fn check_seeded_simulation_matches_exact() -> SelfTestCheck {
+    let mut model = DeadzoneModel::new();
+    model.num_dice = 4;
+    model.num_rerolls = 1;
+    let mut options = DeadzoneOptions::new();
+    options.num_simulations = NUM_SIMULATIONS;
+    let roll_params = RollParams::new(&model, true, &options);
+
+    let mut rng = StdRng::seed_from_u64(SELF_TEST_SEED);
+    let die_distribution = rand::distributions::Uniform::new(PIP_LO, PIP_HI + 1);
+    let success_counts = roll_success_counts(&die_distribution, &mut rng, &roll_params);
+    check_probs_match(
+        "deadzone.seededSimulationMatchesExact",
+        &calc_exact_success_probs(&roll_params),
+        &success_counts_to_probs(&success_counts, NUM_SIMULATIONS),
+        SIMULATED_TOLERANCE,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    // the whole report, so the checks every engine contributes run too
+    #[test]
+    fn self_test_passes() {
+        let report = crate::self_test::self_test();
+        let failures: Vec<String> = report
+            .checks
+            .iter()
+            .filter(|check| !check.passed)
+            .map(|check| format!("{}: {}", check.name, check.details))
+            .collect();
+        assert!(report.all_passed, "failed checks: {}", failures.join("; "));
+    }
+}
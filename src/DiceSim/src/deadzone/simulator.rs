@@ -1,12 +1,30 @@
+use std::cell::RefCell;
 use std::collections::HashMap;
+use std::hash::Hash;
 
 use rand::prelude::*;
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
 use wasm_bindgen::prelude::*;
 
 use super::deadzone_model::DeadzoneModel;
-use super::deadzone_options::DeadzoneOptions;
-use crate::common::ts_types::ToJsMap;
-use crate::common::{add_to_map_value, binomial_pmf, calc_multi_round_damage};
+use super::deadzone_options::{DeadzoneEdition, DeadzoneOptions};
+use super::exact::calc_exact_success_probs;
+use super::house_rules::{adjust_success_counts, pip_success_values};
+use super::hybrid::stitch_exact_tails;
+use super::importance_sampling::roll_importance_sampled_success_probs;
+use super::replay::PipObserver;
+use super::roll_params::{RollParams, PIP_HI, PIP_LO};
+use super::validation::validate_inputs;
+use crate::common::calc_error::CalcError;
+use crate::common::ts_types::{
+    result_schema_v1, CompressedProbs, DenseProbs, PmfCdf, ProbMap, ProbsF32, ToJsMap, TopKProbs,
+    RESULT_SCHEMA_VERSION,
+};
+use crate::common::{
+    add_to_map_value, calc_multi_round_damage, calc_multi_round_damage_by_round, convolve_probs,
+    difference_probs, now_ms, positive_difference_probs, tidy_probs,
+};
 
 #[derive(Default)]
 struct Sf {
@@ -23,104 +41,742 @@ impl Sf {
         self.s += other.s;
         self.f += other.f;
     }
+}
 
-    fn total(&self) -> i32 {
-        self.s + self.f
-    }
+const MAX_CACHED_SUCCESS_PROBS: usize = 64;
+// uncapped explosions get this many rolls per die of room in the dense success tally up front
+const DENSE_ROLLS_PER_UNCAPPED_DIE: i64 = 4;
+// the dense tally starts out no bigger than this and grows if a roll needs more, since a pool
+// of huge dice can't actually roll anywhere near its most successes possible
+const MAX_PREALLOCATED_SUCCESS_COUNTS: usize = 1 << 16;
+
+thread_local! {
+    static SUCCESS_PROBS_CACHE: RefCell<HashMap<RollParams, HashMap<i32, f64>>> =
+        RefCell::new(HashMap::new());
 }
 
-const PIP_LO: i32 = 1;
-const PIP_HI: i32 = 8;
-const SHIELD_SUCCESS_PROB: f64 = 0.375;
+// forget cached simulations, so the next call re-rolls everything
+#[wasm_bindgen(js_name = "deadzoneClearSuccessCache")]
+pub fn deadzone_clear_success_cache() {
+    SUCCESS_PROBS_CACHE.with(|cache| cache.borrow_mut().clear());
+}
 
 #[wasm_bindgen(js_name = "deadzoneCalcDmgProbs")]
 pub fn deadzone_calc_dmg_probs(
     attacker: &DeadzoneModel,
     defender: &DeadzoneModel,
     options: &DeadzoneOptions,
-) -> js_sys::Map {
+) -> Result<js_sys::Map, CalcError> {
+    validate_inputs(attacker, defender, options)?;
+    Ok(tidy_output_probs(&calc_dmg_probs(attacker, defender, options), options).to_js_map())
+}
+
+// dmg probs, and any other distribution handed back to JS, go through the options' output min prob
+// and decimals; calcs that build on dmg probs, like kill probs, use them untidied
+pub fn tidy_output_probs<T: Copy + Eq + Hash>(
+    probs: &HashMap<T, f64>,
+    options: &DeadzoneOptions,
+) -> HashMap<T, f64> {
+    tidy_probs(probs, options.output_min_prob, options.output_decimals)
+}
+
+#[wasm_bindgen(js_name = "deadzoneCalcDmgPmfCdf")]
+pub fn deadzone_calc_dmg_pmf_cdf(
+    attacker: &DeadzoneModel,
+    defender: &DeadzoneModel,
+    options: &DeadzoneOptions,
+) -> Result<PmfCdf, CalcError> {
+    validate_inputs(attacker, defender, options)?;
+    Ok(PmfCdf::from_probs(&tidy_output_probs(
+        &calc_dmg_probs(attacker, defender, options),
+        options,
+    )))
+}
+
+#[wasm_bindgen(js_name = "deadzoneCalcDmgProbsDense")]
+pub fn deadzone_calc_dmg_probs_dense(
+    attacker: &DeadzoneModel,
+    defender: &DeadzoneModel,
+    options: &DeadzoneOptions,
+) -> Result<DenseProbs, CalcError> {
+    validate_inputs(attacker, defender, options)?;
+    Ok(DenseProbs::from_probs(&tidy_output_probs(
+        &calc_dmg_probs(attacker, defender, options),
+        options,
+    )))
+}
+
+#[wasm_bindgen(js_name = "deadzoneCalcDmgProbsTopK")]
+pub fn deadzone_calc_dmg_probs_top_k(
+    attacker: &DeadzoneModel,
+    defender: &DeadzoneModel,
+    options: &DeadzoneOptions,
+    k: usize,
+) -> Result<TopKProbs, CalcError> {
+    validate_inputs(attacker, defender, options)?;
+    Ok(TopKProbs::from_probs(
+        &tidy_output_probs(&calc_dmg_probs(attacker, defender, options), options),
+        k,
+    ))
+}
+
+#[derive(Tsify, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct PhaseTimings {
+    pub roll_ms: f64,       // sampling dice for both models
+    pub accumulate_ms: f64, // turning sample counts into success probs
+    pub combine_ms: f64,    // opposed successes through shields, armor, and toxic
+    pub convolve_ms: f64,   // multiple rounds
+    pub total_ms: f64,
+}
+
+#[derive(Tsify, Serialize, Deserialize)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+#[serde(rename_all = "camelCase")]
+pub struct ProfiledDmgProbs {
+    #[serde(default = "result_schema_v1")]
+    pub result_schema_version: u32,
+    pub dmg_probs: ProbMap,
+    pub timings: PhaseTimings,
+}
+
+// same result as deadzoneCalcDmgProbs, plus how long each phase took
+#[wasm_bindgen(js_name = "deadzoneCalcDmgProbsProfiled")]
+pub fn deadzone_calc_dmg_probs_profiled(
+    attacker: &DeadzoneModel,
+    defender: &DeadzoneModel,
+    options: &DeadzoneOptions,
+) -> Result<ProfiledDmgProbs, CalcError> {
+    validate_inputs(attacker, defender, options)?;
+    let mut timings = PhaseTimings::default();
+    let start_ms = now_ms();
     let mut rng = rand::thread_rng();
     let die_distribution = rand::distributions::Uniform::new(PIP_LO, PIP_HI + 1);
-    let atk_success_probs = make_success_probs(&die_distribution, &mut rng, &attacker, &options);
-    let def_success_probs = make_success_probs(&die_distribution, &mut rng, &defender, &options);
-    let mut dmg_probs = HashMap::<i32, f64>::new();
+    let atk_roll_params = RollParams::new(attacker, true, options);
+    let def_roll_params = RollParams::new(defender, false, options);
+
+    // bypasses the success probs cache so the roll timing is meaningful;
+    // for anything but plain simulation, all the success probs work counts as rolling
+    let is_plain_simulation = options.num_simulations != 0
+        && atk_roll_params.importance_sampling_success_prob_bits == 0
+        && atk_roll_params.exact_tail_mass_bits == 0;
+    let (atk_success_probs, def_success_probs, rolled_ms) = if is_plain_simulation {
+        let atk_success_counts = roll_success_counts(&die_distribution, &mut rng, &atk_roll_params);
+        let def_success_counts = roll_success_counts(&die_distribution, &mut rng, &def_roll_params);
+        let rolled_ms = now_ms();
+        (
+            success_counts_to_probs(&atk_success_counts, options.num_simulations),
+            success_counts_to_probs(&def_success_counts, options.num_simulations),
+            rolled_ms,
+        )
+    } else {
+        let atk_success_probs =
+            calc_uncached_success_probs(&die_distribution, &mut rng, &atk_roll_params);
+        let def_success_probs =
+            calc_uncached_success_probs(&die_distribution, &mut rng, &def_roll_params);
+        (atk_success_probs, def_success_probs, now_ms())
+    };
+    timings.roll_ms = rolled_ms - start_ms;
+    let accumulated_ms = now_ms();
+    timings.accumulate_ms = accumulated_ms - rolled_ms;
+
+    let mut dmg_probs = combine_opposed_successes(
+        attacker,
+        defender,
+        options,
+        &atk_success_probs,
+        &def_success_probs,
+    );
+    let combined_ms = now_ms();
+    timings.combine_ms = combined_ms - accumulated_ms;
+
+    if options.num_rounds > 1 {
+        dmg_probs = calc_multi_round_damage(&dmg_probs, options.num_rounds);
+    }
+    let convolved_ms = now_ms();
+    timings.convolve_ms = convolved_ms - combined_ms;
+    timings.total_ms = convolved_ms - start_ms;
+
+    Ok(ProfiledDmgProbs {
+        result_schema_version: RESULT_SCHEMA_VERSION,
+        dmg_probs: ProbMap(tidy_output_probs(&dmg_probs, options)),
+        timings,
+    })
+}
+
+#[wasm_bindgen(js_name = "deadzoneCalcDmgProbsF32")]
+pub fn deadzone_calc_dmg_probs_f32(
+    attacker: &DeadzoneModel,
+    defender: &DeadzoneModel,
+    options: &DeadzoneOptions,
+) -> Result<ProbsF32, CalcError> {
+    validate_inputs(attacker, defender, options)?;
+    Ok(ProbsF32::from_probs(&tidy_output_probs(
+        &calc_dmg_probs(attacker, defender, options),
+        options,
+    )))
+}
+
+// buckets below epsilon outside the kept range are merged into boundary markers
+#[wasm_bindgen(js_name = "deadzoneCalcDmgProbsCompressed")]
+pub fn deadzone_calc_dmg_probs_compressed(
+    attacker: &DeadzoneModel,
+    defender: &DeadzoneModel,
+    options: &DeadzoneOptions,
+    epsilon: f64,
+    run_length_encode: bool,
+) -> Result<CompressedProbs, CalcError> {
+    validate_inputs(attacker, defender, options)?;
+    Ok(CompressedProbs::from_probs(
+        &tidy_output_probs(&calc_dmg_probs(attacker, defender, options), options),
+        epsilon,
+        run_length_encode,
+    ))
+}
+
+pub fn calc_dmg_probs(
+    attacker: &DeadzoneModel,
+    defender: &DeadzoneModel,
+    options: &DeadzoneOptions,
+) -> HashMap<i32, f64> {
+    let dmg_probs = calc_single_round_dmg_probs(attacker, defender, options);
+    calc_multi_round_dmg_probs(&dmg_probs, attacker, defender, options)
+}
+
+// repeats a single round's dmg for the options' rounds
+pub fn calc_multi_round_dmg_probs(
+    single_round_dmg_probs: &HashMap<i32, f64>,
+    attacker: &DeadzoneModel,
+    defender: &DeadzoneModel,
+    options: &DeadzoneOptions,
+) -> HashMap<i32, f64> {
+    if options.num_rounds <= 1 {
+        single_round_dmg_probs.clone()
+    } else if options.stop_when_dead {
+        calc_dmg_probs_by_round_until_dead(
+            single_round_dmg_probs,
+            attacker,
+            defender,
+            options.num_rounds,
+        )
+        .pop()
+        .unwrap()
+    } else {
+        calc_multi_round_damage(single_round_dmg_probs, options.num_rounds)
+    }
+}
+
+// element i is the cumulative dmg distribution after round i+1
+pub fn calc_dmg_probs_by_round(
+    attacker: &DeadzoneModel,
+    defender: &DeadzoneModel,
+    options: &DeadzoneOptions,
+) -> Vec<HashMap<i32, f64>> {
+    let single_round_dmg_probs = calc_single_round_dmg_probs(attacker, defender, options);
+    if options.stop_when_dead {
+        calc_dmg_probs_by_round_until_dead(
+            &single_round_dmg_probs,
+            attacker,
+            defender,
+            options.num_rounds,
+        )
+    } else {
+        calc_multi_round_damage_by_round(&single_round_dmg_probs, options.num_rounds)
+    }
+}
 
-    for (atk_successes, atk_prob) in atk_success_probs.iter() {
-        for (def_successes, def_prob) in def_success_probs.iter() {
-            let mut orig_dmg = atk_successes - def_successes;
+// like convolving rounds, but each model's dmg taken is tracked separately so that a fight is over
+// once either model is dead; the round that kills still counts in full, so overkill is unchanged
+fn calc_dmg_probs_by_round_until_dead(
+    single_round_dmg_probs: &HashMap<i32, f64>,
+    attacker: &DeadzoneModel,
+    defender: &DeadzoneModel,
+    num_rounds: i32,
+) -> Vec<HashMap<i32, f64>> {
+    // (dmg taken by attacker, dmg taken by defender)
+    let mut joint_dmg_probs = HashMap::from([((0, 0), 1.0)]);
+    let mut dmg_probs_by_round = Vec::new();
 
-            if !options.attacker_can_be_damaged {
-                orig_dmg = std::cmp::max(0, orig_dmg);
+    for _round_number in 1..=std::cmp::max(1, num_rounds) {
+        let mut next_joint_dmg_probs = HashMap::<(i32, i32), f64>::new();
+        for ((atk_dmg_taken, def_dmg_taken), joint_prob) in joint_dmg_probs.iter() {
+            if *atk_dmg_taken >= attacker.hp || *def_dmg_taken >= defender.hp {
+                add_to_map_value(
+                    &mut next_joint_dmg_probs,
+                    &(*atk_dmg_taken, *def_dmg_taken),
+                    *joint_prob,
+                );
+                continue;
             }
+            for (dmg, round_prob) in single_round_dmg_probs.iter() {
+                add_to_map_value(
+                    &mut next_joint_dmg_probs,
+                    &(
+                        atk_dmg_taken + std::cmp::max(0, -dmg),
+                        def_dmg_taken + std::cmp::max(0, *dmg),
+                    ),
+                    joint_prob * round_prob,
+                );
+            }
+        }
+        joint_dmg_probs = next_joint_dmg_probs;
 
-            let (dmg_giver, dmg_receiver) = if orig_dmg >= 0 {
-                (&attacker, &defender)
-            } else {
-                (&defender, &attacker)
+        let mut dmg_probs = HashMap::<i32, f64>::new();
+        for ((atk_dmg_taken, def_dmg_taken), prob) in joint_dmg_probs.iter() {
+            add_to_map_value(&mut dmg_probs, &(def_dmg_taken - atk_dmg_taken), *prob);
+        }
+        dmg_probs_by_round.push(dmg_probs);
+    }
+    dmg_probs_by_round
+}
+
+// positive damage is dealt to the defender, negative damage to the attacker
+pub fn calc_single_round_dmg_probs(
+    attacker: &DeadzoneModel,
+    defender: &DeadzoneModel,
+    options: &DeadzoneOptions,
+) -> HashMap<i32, f64> {
+    let (atk_success_probs, def_success_probs) =
+        calc_success_probs_pair(attacker, defender, options);
+    combine_opposed_successes(
+        attacker,
+        defender,
+        options,
+        &atk_success_probs,
+        &def_success_probs,
+    )
+}
+
+// attacker's successes minus defender's successes, before any shields or armor; cover dice count
+// for the defender when shooting
+pub fn calc_success_margin_probs(
+    attacker: &DeadzoneModel,
+    defender: &DeadzoneModel,
+    options: &DeadzoneOptions,
+) -> HashMap<i32, f64> {
+    let (atk_success_probs, def_success_probs) =
+        calc_success_probs_pair(attacker, defender, options);
+    opposed_margin_probs(options, &atk_success_probs, &def_success_probs)
+}
+
+// how much the attacker wins the roll by, with ties and lost rolls both counted as 0
+pub fn calc_winning_margin_probs(
+    attacker: &DeadzoneModel,
+    defender: &DeadzoneModel,
+    options: &DeadzoneOptions,
+) -> HashMap<i32, f64> {
+    let (atk_success_probs, def_success_probs) =
+        calc_success_probs_pair(attacker, defender, options);
+    positive_difference_probs(
+        &atk_success_probs,
+        &defender_total_success_probs(options, &def_success_probs),
+    )
+}
+
+// margins are kept signed even when shooting, so a lost roll can be told apart from a tie
+fn opposed_margin_probs(
+    options: &DeadzoneOptions,
+    atk_success_probs: &HashMap<i32, f64>,
+    def_success_probs: &HashMap<i32, f64>,
+) -> HashMap<i32, f64> {
+    difference_probs(
+        atk_success_probs,
+        &defender_total_success_probs(options, def_success_probs),
+    )
+}
+
+// cover dice add to the defender's successes, but only when shooting
+fn defender_total_success_probs(
+    options: &DeadzoneOptions,
+    def_success_probs: &HashMap<i32, f64>,
+) -> HashMap<i32, f64> {
+    if !options.attacker_can_be_damaged && options.num_cover_dice > 0 {
+        convolve_probs(def_success_probs, &calc_cover_success_probs(options))
+    } else {
+        def_success_probs.clone()
+    }
+}
+
+pub fn calc_success_probs_pair(
+    attacker: &DeadzoneModel,
+    defender: &DeadzoneModel,
+    options: &DeadzoneOptions,
+) -> (HashMap<i32, f64>, HashMap<i32, f64>) {
+    (
+        calc_success_probs(attacker, true, options),
+        calc_success_probs(defender, false, options),
+    )
+}
+
+pub fn calc_success_probs(
+    model: &DeadzoneModel,
+    is_attacker: bool,
+    options: &DeadzoneOptions,
+) -> HashMap<i32, f64> {
+    let mut rng = rand::thread_rng();
+    let die_distribution = rand::distributions::Uniform::new(PIP_LO, PIP_HI + 1);
+    make_success_probs(
+        &die_distribution,
+        &mut rng,
+        &RollParams::new(model, is_attacker, options),
+    )
+}
+
+pub fn combine_opposed_successes(
+    attacker: &DeadzoneModel,
+    defender: &DeadzoneModel,
+    options: &DeadzoneOptions,
+    atk_success_probs: &HashMap<i32, f64>,
+    def_success_probs: &HashMap<i32, f64>,
+) -> HashMap<i32, f64> {
+    let mut dmg_probs = HashMap::<i32, f64>::new();
+    for_each_opposed_outcome(
+        attacker,
+        defender,
+        options,
+        atk_success_probs,
+        def_success_probs,
+        |outcome, prob| add_to_map_value(&mut dmg_probs, &outcome.dmg, prob),
+    );
+    dmg_probs
+}
+
+// one way an opposed roll can turn out, down to the shield roll
+#[derive(Clone, Copy)]
+pub struct OpposedOutcome {
+    pub dmg: i32, // positive to the defender and negative to the attacker, like everywhere else
+    pub is_instant_kill: bool,
+    pub zero_dmg_cause: Option<ZeroDmgCause>, // set whenever the defender took no dmg
+}
+
+// why the defender took no dmg, with the earliest step of the resolution that stopped it
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ZeroDmgCause {
+    TiedRoll,
+    AttackerLostRoll, // in a Fight, the attacker may take dmg instead
+    ShieldsAbsorbed,
+    ArmorAbsorbed,
+    DmgCapped, // by a maxDmg of 0
+}
+
+// calls visit with every outcome and its prob, for anything needing more than the dmg
+pub fn for_each_opposed_outcome(
+    attacker: &DeadzoneModel,
+    defender: &DeadzoneModel,
+    options: &DeadzoneOptions,
+    atk_success_probs: &HashMap<i32, f64>,
+    def_success_probs: &HashMap<i32, f64>,
+    mut visit: impl FnMut(&OpposedOutcome, f64),
+) {
+    let no_shield_probs = HashMap::from([(0, 1.0)]);
+    let atk_shield_probs = calc_shield_success_probs(attacker);
+    let def_shield_probs = calc_shield_success_probs(defender);
+
+    // outcomes only depend on the margin, so each margin is resolved once
+    let margin_probs = opposed_margin_probs(options, atk_success_probs, def_success_probs);
+
+    for (&margin, margin_prob) in margin_probs.iter() {
+        // when shooting, the attacker can't be hurt by losing the roll
+        let orig_dmg = if options.attacker_can_be_damaged {
+            margin
+        } else {
+            std::cmp::max(0, margin)
+        };
+        let lost_roll_cause = match margin.signum() {
+            0 => Some(ZeroDmgCause::TiedRoll),
+            -1 => Some(ZeroDmgCause::AttackerLostRoll),
+            _ => None,
+        };
+        let (dmg_giver, dmg_receiver) = if orig_dmg >= 0 {
+            (attacker, defender)
+        } else {
+            (defender, attacker)
+        };
+
+        // an instant kill ignores shields, armor and the dmg cap
+        if orig_dmg != 0 && orig_dmg.abs() >= dmg_giver.instant_kill_margin {
+            let outcome = OpposedOutcome {
+                dmg: orig_dmg.signum() * dmg_receiver.hp,
+                is_instant_kill: true,
+                zero_dmg_cause: lost_roll_cause,
             };
-            let net_armor = std::cmp::max(0, dmg_receiver.armor - dmg_giver.ap);
-            let num_shield_dice = if orig_dmg == 0 {
-                0
+            visit(&outcome, *margin_prob);
+            continue;
+        }
+
+        let ap =
+            dmg_giver.ap + dmg_giver.ap_per_excess_success * std::cmp::max(0, orig_dmg.abs() - 1);
+        let net_armor = std::cmp::max(0, dmg_receiver.armor - ap);
+        let shield_success_probs = if orig_dmg == 0 {
+            &no_shield_probs
+        } else if orig_dmg > 0 {
+            &def_shield_probs
+        } else {
+            &atk_shield_probs
+        };
+
+        for (shield_successes, shield_prob) in shield_success_probs.iter() {
+            let unmitigated_dmg = margin_to_dmg(orig_dmg.abs(), dmg_giver.successes_per_dmg);
+            let uncapped_dmg = resolve_dmg(
+                unmitigated_dmg,
+                *shield_successes,
+                net_armor,
+                dmg_giver.toxic_dmg,
+                options.edition,
+            );
+            let final_dmg = uncapped_dmg.min(dmg_giver.max_dmg);
+            let zero_dmg_cause = if lost_roll_cause.is_some() {
+                lost_roll_cause
+            } else if uncapped_dmg == 0 {
+                Some(absorbing_cause(
+                    unmitigated_dmg,
+                    *shield_successes,
+                    net_armor,
+                    options.edition,
+                ))
+            } else if final_dmg == 0 {
+                Some(ZeroDmgCause::DmgCapped)
             } else {
-                dmg_receiver.num_shield_dice
+                None
             };
-            let atk_and_def_prob = atk_prob * def_prob;
-
-            for shield_successes in 0..=num_shield_dice {
-                let shield_prob = if num_shield_dice == 0 {
-                    1.0
-                } else {
-                    binomial_pmf(num_shield_dice, shield_successes, SHIELD_SUCCESS_PROB)
-                };
-                let post_shield_dmg = std::cmp::max(0, orig_dmg.abs() - shield_successes);
-                let post_armor_dmg = std::cmp::max(0, post_shield_dmg - net_armor);
-                let post_toxic_dmg = post_armor_dmg + dmg_giver.toxic_dmg;
-                add_to_map_value(
-                    &mut dmg_probs,
-                    &(orig_dmg.signum() * post_toxic_dmg),
-                    atk_and_def_prob * shield_prob,
-                );
-            }
+            let outcome = OpposedOutcome {
+                dmg: orig_dmg.signum() * final_dmg,
+                is_instant_kill: false,
+                zero_dmg_cause,
+            };
+            visit(&outcome, margin_prob * shield_prob);
         }
     }
-    if options.num_rounds > 1 {
-        dmg_probs = calc_multi_round_damage(&dmg_probs, options.num_rounds);
+}
+
+fn calc_cover_success_probs(options: &DeadzoneOptions) -> HashMap<i32, f64> {
+    let mut rng = rand::thread_rng();
+    let die_distribution = rand::distributions::Uniform::new(PIP_LO, PIP_HI + 1);
+    make_success_probs(&die_distribution, &mut rng, &RollParams::for_cover(options))
+}
+
+fn calc_shield_success_probs(model: &DeadzoneModel) -> HashMap<i32, f64> {
+    let mut rng = rand::thread_rng();
+    let die_distribution = rand::distributions::Uniform::new(PIP_LO, PIP_HI + 1);
+    make_success_probs(&die_distribution, &mut rng, &RollParams::for_shields(model))
+}
+
+// dmg before mitigation from the opposed roll's margin of successes
+fn margin_to_dmg(margin: i32, successes_per_dmg: i32) -> i32 {
+    if successes_per_dmg <= 1 {
+        margin
+    } else {
+        (margin + successes_per_dmg - 1) / successes_per_dmg
     }
-    return dmg_probs.to_js_map();
 }
 
+// for dmg that resolve_dmg took all the way to 0; in 2nd edition, only the better of shields and
+// armor counts, so that's the one that absorbed it
+fn absorbing_cause(
+    orig_dmg: i32,
+    shield_successes: i32,
+    net_armor: i32,
+    edition: DeadzoneEdition,
+) -> ZeroDmgCause {
+    let shields_absorbed = match edition {
+        DeadzoneEdition::Third => shield_successes >= orig_dmg,
+        DeadzoneEdition::Second => shield_successes >= net_armor,
+    };
+    if shields_absorbed {
+        ZeroDmgCause::ShieldsAbsorbed
+    } else {
+        ZeroDmgCause::ArmorAbsorbed
+    }
+}
+
+// dmg that gets through shields and armor, plus toxic dmg
+fn resolve_dmg(
+    orig_dmg: i32,
+    shield_successes: i32,
+    net_armor: i32,
+    toxic_dmg: i32,
+    edition: DeadzoneEdition,
+) -> i32 {
+    match edition {
+        DeadzoneEdition::Third => {
+            let post_shield_dmg = std::cmp::max(0, orig_dmg - shield_successes);
+            let post_armor_dmg = std::cmp::max(0, post_shield_dmg - net_armor);
+            post_armor_dmg + toxic_dmg
+        }
+        DeadzoneEdition::Second => {
+            let post_armor_dmg =
+                std::cmp::max(0, orig_dmg - std::cmp::max(shield_successes, net_armor));
+            if post_armor_dmg > 0 {
+                post_armor_dmg + toxic_dmg
+            } else {
+                0
+            }
+        }
+    }
+}
+
+// numSimulations=0 means use the exact engine instead of simulating;
+// success distributions are cached by their roll params, so a call that only changes
+// things like numRounds or armor reuses the previous call's simulations
 fn make_success_probs(
     die_distribution: &rand::distributions::Uniform<i32>,
     rng: &mut ThreadRng,
-    model: &DeadzoneModel,
-    options: &DeadzoneOptions,
+    roll_params: &RollParams,
+) -> HashMap<i32, f64> {
+    if let Some(success_probs) =
+        SUCCESS_PROBS_CACHE.with(|cache| cache.borrow().get(roll_params).cloned())
+    {
+        return success_probs;
+    }
+
+    let success_probs = calc_uncached_success_probs(die_distribution, rng, roll_params);
+    SUCCESS_PROBS_CACHE.with(|cache| {
+        let mut cache = cache.borrow_mut();
+        if cache.len() >= MAX_CACHED_SUCCESS_PROBS {
+            cache.clear();
+        }
+        cache.insert(*roll_params, success_probs.clone());
+    });
+    success_probs
+}
+
+fn calc_uncached_success_probs(
+    die_distribution: &rand::distributions::Uniform<i32>,
+    rng: &mut ThreadRng,
+    roll_params: &RollParams,
 ) -> HashMap<i32, f64> {
-    let mut success_counts = HashMap::<i32, i32>::new();
-    for _ in 0..options.num_simulations {
-        let num_successes = simulated_num_successes_from_multi_roll(
+    if roll_params.num_simulations == 0 {
+        return calc_exact_success_probs(roll_params);
+    }
+    // the tilted die only reweighs whole-pool totals, so pools ranking their dice are sampled plainly
+    let simulated_probs =
+        if roll_params.importance_sampling_success_prob_bits != 0 && !roll_params.ranks_dice() {
+            roll_importance_sampled_success_probs(rng, roll_params)
+        } else {
+            let success_counts = roll_success_counts(die_distribution, rng, roll_params);
+            success_counts_to_probs(&success_counts, roll_params.num_simulations)
+        };
+    if roll_params.exact_tail_mass_bits != 0 {
+        stitch_exact_tails(
+            &simulated_probs,
+            roll_params,
+            f64::from_bits(roll_params.exact_tail_mass_bits),
+        )
+    } else {
+        simulated_probs
+    }
+}
+
+// successes are tallied in a dense vec indexed by success count, which avoids hashing in the hot loop;
+// it's sized to the most successes possible, or grows on the rare long explosion chain if uncapped
+pub fn roll_success_counts<R: Rng>(
+    die_distribution: &rand::distributions::Uniform<i32>,
+    rng: &mut R,
+    roll_params: &RollParams,
+) -> HashMap<i32, i32> {
+    roll_observed_success_counts(die_distribution, rng, roll_params, &mut ())
+}
+
+// same as roll_success_counts, but tells the observer about every pip rolled
+pub fn roll_observed_success_counts<R: Rng, O: PipObserver>(
+    die_distribution: &rand::distributions::Uniform<i32>,
+    rng: &mut R,
+    roll_params: &RollParams,
+    observer: &mut O,
+) -> HashMap<i32, i32> {
+    let success_value_by_pip = pip_success_values(roll_params);
+    // validate_inputs rejects roll params whose tally size overflows
+    let dense_len = dense_success_counts_len(roll_params, &success_value_by_pip).unwrap_or(0);
+    let mut dense_success_counts =
+        vec![0; std::cmp::min(dense_len, MAX_PREALLOCATED_SUCCESS_COUNTS)];
+
+    let mut die_successes = Vec::new();
+    for _ in 0..roll_params.num_simulations {
+        let num_successes = simulated_num_successes(
             die_distribution,
             rng,
-            model.num_dice,
-            model.dice_stat,
-            model.num_rerolls,
-            options.exploding_dice_max_levels,
-        );
-        add_to_map_value(&mut success_counts, &num_successes, 1);
+            roll_params,
+            &success_value_by_pip,
+            &mut die_successes,
+            observer,
+        ) as usize;
+        observer.on_roll_end();
+        if num_successes >= dense_success_counts.len() {
+            dense_success_counts.resize(num_successes + 1, 0);
+        }
+        dense_success_counts[num_successes] += 1;
     }
-    let success_probs = success_counts
+
+    let success_counts = dense_success_counts
         .iter()
-        .map(|(k, v)| (*k, *v as f64 / options.num_simulations as f64))
+        .enumerate()
+        .filter(|(_, count)| **count > 0)
+        .map(|(num_successes, count)| (num_successes as i32, *count))
         .collect();
-    return success_probs;
+    adjust_success_counts(success_counts, roll_params)
 }
 
-fn simulated_num_successes_from_multi_roll(
+// room for the most successes possible, with uncapped explosions given DENSE_ROLLS_PER_UNCAPPED_DIE
+// rolls per die; done in i64 since dice, rerolls and success values can each be big
+pub fn dense_success_counts_len(
+    roll_params: &RollParams,
+    success_value_by_pip: &[i32],
+) -> Result<usize, CalcError> {
+    let max_success_value = *success_value_by_pip.iter().max().unwrap() as i64;
+    let num_die_rolls = std::cmp::max(0, roll_params.num_dice) as i64
+        + std::cmp::max(0, roll_params.num_rerolls) as i64;
+    let max_rolls_per_die =
+        if (roll_params.exploding_dice_max_levels as i64) < DENSE_ROLLS_PER_UNCAPPED_DIE {
+            std::cmp::max(0, roll_params.exploding_dice_max_levels) as i64 + 1
+        } else {
+            DENSE_ROLLS_PER_UNCAPPED_DIE
+        };
+    num_die_rolls
+        .checked_mul(max_rolls_per_die)
+        .and_then(|num_rolls| num_rolls.checked_mul(max_success_value))
+        .and_then(|max_successes| max_successes.checked_add(1))
+        .and_then(|len| usize::try_from(len).ok())
+        .ok_or(CalcError::Overflow)
+}
+
+// one roll's successes, for callers that need individual samples rather than a distribution
+pub fn roll_num_successes<R: Rng>(
     die_distribution: &rand::distributions::Uniform<i32>,
-    rng: &mut ThreadRng,
+    rng: &mut R,
+    roll_params: &RollParams,
+) -> i32 {
+    let num_successes = simulated_num_successes(
+        die_distribution,
+        rng,
+        roll_params,
+        &pip_success_values(roll_params),
+        &mut Vec::new(),
+        &mut (),
+    );
+    *adjust_success_counts(HashMap::from([(num_successes, 1)]), roll_params)
+        .keys()
+        .next()
+        .unwrap()
+}
+
+pub fn success_counts_to_probs(
+    success_counts: &HashMap<i32, i32>,
+    num_simulations: i32,
+) -> HashMap<i32, f64> {
+    success_counts
+        .iter()
+        .map(|(k, v)| (*k, *v as f64 / num_simulations as f64))
+        .collect()
+}
+
+fn simulated_num_successes_from_multi_roll<R: Rng, O: PipObserver>(
+    die_distribution: &rand::distributions::Uniform<i32>,
+    rng: &mut R,
     num_dice: i32,
-    dice_stat: i32,
+    success_value_by_pip: &[i32],
     num_rerolls: i32,
     exploding_dice_max_levels: i32,
+    observer: &mut O,
 ) -> i32 {
     let mut sf = Sf::new();
 
@@ -128,8 +784,9 @@ fn simulated_num_successes_from_multi_roll(
         sf.add(&simulated_sf_from_single_roll(
             die_distribution,
             rng,
-            dice_stat,
+            success_value_by_pip,
             exploding_dice_max_levels,
+            observer,
         ));
     }
 
@@ -138,35 +795,285 @@ fn simulated_num_successes_from_multi_roll(
         0
     } else {
         let num_actual_rerolls = std::cmp::min(num_rerolls, sf.f);
+        observer.on_rerolls();
         simulated_num_successes_from_multi_roll(
             die_distribution,
             rng,
             num_actual_rerolls,
-            dice_stat,
+            success_value_by_pip,
             0,
             exploding_dice_max_levels,
+            observer,
         )
     };
-    return num_original_successes + num_rerolled_successes;
+    num_original_successes + num_rerolled_successes
 }
 
-fn simulated_sf_from_single_roll(
+// die_successes is scratch space for pools that keep only their best dice
+fn simulated_num_successes<R: Rng, O: PipObserver>(
     die_distribution: &rand::distributions::Uniform<i32>,
-    rng: &mut ThreadRng,
-    dice_stat: i32,
+    rng: &mut R,
+    roll_params: &RollParams,
+    success_value_by_pip: &[i32],
+    die_successes: &mut Vec<i32>,
+    observer: &mut O,
+) -> i32 {
+    if roll_params.ranks_dice() {
+        simulated_num_successes_keeping_best(
+            die_distribution,
+            rng,
+            roll_params,
+            success_value_by_pip,
+            die_successes,
+            observer,
+        )
+    } else {
+        simulated_num_successes_from_multi_roll(
+            die_distribution,
+            rng,
+            roll_params.num_dice,
+            success_value_by_pip,
+            roll_params.num_rerolls,
+            roll_params.exploding_dice_max_levels,
+            observer,
+        )
+    }
+}
+
+// like simulated_num_successes_from_multi_roll, but each die (rerolled ones included) is ranked
+// by its own successes and only the best roll_params.num_dice_kept count, after the opponent forces
+// the best successes to be rerolled
+fn simulated_num_successes_keeping_best<R: Rng, O: PipObserver>(
+    die_distribution: &rand::distributions::Uniform<i32>,
+    rng: &mut R,
+    roll_params: &RollParams,
+    success_value_by_pip: &[i32],
+    die_successes: &mut Vec<i32>,
+    observer: &mut O,
+) -> i32 {
+    die_successes.clear();
+    let mut num_failures = 0;
+    for _ in 0..roll_params.num_dice {
+        let sf = simulated_sf_from_single_roll(
+            die_distribution,
+            rng,
+            success_value_by_pip,
+            roll_params.exploding_dice_max_levels,
+            observer,
+        );
+        die_successes.push(sf.s);
+        num_failures += sf.f;
+    }
+
+    let num_actual_rerolls = std::cmp::min(roll_params.num_rerolls, num_failures);
+    if num_actual_rerolls > 0 {
+        observer.on_rerolls();
+        for _ in 0..num_actual_rerolls {
+            let sf = simulated_sf_from_single_roll(
+                die_distribution,
+                rng,
+                success_value_by_pip,
+                roll_params.exploding_dice_max_levels,
+                observer,
+            );
+            die_successes.push(sf.s);
+        }
+    }
+
+    die_successes.sort_unstable_by(|a, b| b.cmp(a));
+    // forced rerolls only hit dice with successes, and their new rolls can't be rerolled again
+    let num_actual_forced_rerolls = die_successes
+        .iter()
+        .take(std::cmp::max(0, roll_params.num_forced_rerolls) as usize)
+        .filter(|successes| **successes > 0)
+        .count();
+    if num_actual_forced_rerolls > 0 {
+        observer.on_rerolls();
+        for successes in die_successes.iter_mut().take(num_actual_forced_rerolls) {
+            *successes = simulated_sf_from_single_roll(
+                die_distribution,
+                rng,
+                success_value_by_pip,
+                roll_params.exploding_dice_max_levels,
+                observer,
+            )
+            .s;
+        }
+        die_successes.sort_unstable_by(|a, b| b.cmp(a));
+    }
+    die_successes
+        .iter()
+        .take(roll_params.num_dice_kept as usize)
+        .sum()
+}
+
+fn simulated_sf_from_single_roll<R: Rng, O: PipObserver>(
+    die_distribution: &rand::distributions::Uniform<i32>,
+    rng: &mut R,
+    success_value_by_pip: &[i32],
     exploding_dice_max_levels: i32,
+    observer: &mut O,
 ) -> Sf {
     let mut sf = Sf::new();
+    let mut num_rolls = 0;
     loop {
         let pip_outcome = die_distribution.sample(rng);
-        if pip_outcome >= dice_stat {
-            sf.s += 1;
+        observer.on_pip(pip_outcome);
+        num_rolls += 1;
+        let success_value = success_value_by_pip[pip_outcome as usize];
+        if success_value > 0 {
+            sf.s += success_value;
         } else {
             sf.f += 1;
         }
-        if pip_outcome != PIP_HI || sf.total() > exploding_dice_max_levels {
+        if pip_outcome != PIP_HI || num_rolls > exploding_dice_max_levels {
             break;
         }
     }
-    return sf;
+    sf
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn third_edition_stacks_shields_and_armor() {
+        assert_eq!(resolve_dmg(5, 2, 1, 0, DeadzoneEdition::Third), 2);
+        assert_eq!(resolve_dmg(2, 1, 1, 0, DeadzoneEdition::Third), 0);
+    }
+
+    #[test]
+    fn third_edition_toxic_applies_even_if_absorbed() {
+        assert_eq!(resolve_dmg(2, 1, 1, 1, DeadzoneEdition::Third), 1);
+        assert_eq!(resolve_dmg(3, 0, 1, 1, DeadzoneEdition::Third), 3);
+    }
+
+    #[test]
+    fn second_edition_uses_better_of_shields_and_armor() {
+        assert_eq!(resolve_dmg(5, 2, 1, 0, DeadzoneEdition::Second), 3);
+        assert_eq!(resolve_dmg(5, 1, 3, 0, DeadzoneEdition::Second), 2);
+    }
+
+    #[test]
+    fn second_edition_toxic_needs_dmg_through() {
+        assert_eq!(resolve_dmg(2, 2, 0, 1, DeadzoneEdition::Second), 0);
+        assert_eq!(resolve_dmg(3, 2, 0, 1, DeadzoneEdition::Second), 2);
+    }
+
+    #[test]
+    fn margin_to_dmg_rounds_up() {
+        assert_eq!(margin_to_dmg(0, 2), 0);
+        assert_eq!(margin_to_dmg(1, 2), 1);
+        assert_eq!(margin_to_dmg(2, 2), 1);
+        assert_eq!(margin_to_dmg(3, 2), 2);
+        assert_eq!(margin_to_dmg(5, 1), 5);
+    }
+
+    #[test]
+    fn stopping_when_dead_skips_later_rounds() {
+        let attacker = DeadzoneModel::new();
+        let defender = DeadzoneModel::new();
+        let dmg_probs_by_round =
+            calc_dmg_probs_by_round_until_dead(&HashMap::from([(1, 1.0)]), &attacker, &defender, 3);
+        assert_eq!(dmg_probs_by_round.len(), 3);
+        assert_eq!(dmg_probs_by_round[2], HashMap::from([(defender.hp, 1.0)]));
+    }
+
+    // 2 billion dice with 8s worth i32::MAX successes can't fit a tally, even in i64
+    #[test]
+    fn oversized_success_tally_is_rejected() {
+        let mut model = DeadzoneModel::new();
+        model.num_dice = 2_000_000_000;
+        let roll_params = RollParams::new(&model, true, &DeadzoneOptions::new());
+        let mut success_value_by_pip = [0; (PIP_HI + 1) as usize];
+        success_value_by_pip[PIP_HI as usize] = i32::MAX;
+        assert_eq!(
+            dense_success_counts_len(&roll_params, &success_value_by_pip),
+            Err(CalcError::Overflow)
+        );
+
+        model.num_dice = 3;
+        model
+            .set_face_success_values(vec![0, 0, 0, 0, 0, 0, 1, 2])
+            .unwrap();
+        let roll_params = RollParams::new(&model, true, &DeadzoneOptions::new());
+        let dense_len = dense_success_counts_len(&roll_params, &pip_success_values(&roll_params));
+        assert_eq!(dense_len, Ok(3 * 4 * 2 + 1));
+    }
+
+    // setFaceSuccessValues checks the values, and so does validation for deserialized models
+    #[test]
+    fn face_success_values_are_bounded() {
+        let mut model = DeadzoneModel::new();
+        assert_eq!(
+            model.set_face_success_values(vec![0, 0, 0, 0, 0, 0, 1, 11]),
+            Err(CalcError::InvalidStat)
+        );
+        assert_eq!(
+            model.set_face_success_values(vec![0, 0, 0, 0, 0, 0, 1, 10]),
+            Ok(())
+        );
+        let model: DeadzoneModel =
+            serde_json::from_str(r#"{"faceSuccessValues": [0, 0, 0, 0, 0, 0, 1, 11]}"#).unwrap();
+        assert_eq!(
+            validate_inputs(&model, &DeadzoneModel::new(), &DeadzoneOptions::new()),
+            Err(CalcError::InvalidStat)
+        );
+    }
+
+    #[test]
+    fn editions_differ_in_opposed_roll() {
+        let mut attacker = DeadzoneModel::new();
+        attacker.toxic_dmg = 1;
+        let mut defender = DeadzoneModel::new();
+        defender.armor = 1;
+        let mut options = DeadzoneOptions::new();
+        // attacker always gets 1 success more than the defender, which armor absorbs
+        let atk_success_probs = HashMap::from([(2, 1.0)]);
+        let def_success_probs = HashMap::from([(1, 1.0)]);
+
+        options.edition = DeadzoneEdition::Third;
+        let third = combine_opposed_successes(
+            &attacker,
+            &defender,
+            &options,
+            &atk_success_probs,
+            &def_success_probs,
+        );
+        assert_eq!(third, HashMap::from([(1, 1.0)]));
+
+        options.edition = DeadzoneEdition::Second;
+        let second = combine_opposed_successes(
+            &attacker,
+            &defender,
+            &options,
+            &atk_success_probs,
+            &def_success_probs,
+        );
+        assert_eq!(second, HashMap::from([(0, 1.0)]));
+    }
+
+    #[test]
+    fn cover_dice_count_against_the_success_margin() {
+        let mut attacker = DeadzoneModel::new();
+        attacker.num_dice = 0;
+        let mut defender = DeadzoneModel::new();
+        defender.num_dice = 0;
+        let mut options = DeadzoneOptions::new();
+        options.num_simulations = 0;
+        options.exploding_dice_max_levels = 0;
+        options.num_cover_dice = 1;
+        options.cover_dice_stat = 4;
+        let margin_probs = calc_success_margin_probs(&attacker, &defender, &options);
+        assert_eq!(
+            margin_probs,
+            HashMap::from([(0, 3.0 / 8.0), (-1, 5.0 / 8.0)])
+        );
+
+        // cover only helps against shooting
+        options.attacker_can_be_damaged = true;
+        let margin_probs = calc_success_margin_probs(&attacker, &defender, &options);
+        assert_eq!(margin_probs, HashMap::from([(0, 1.0)]));
+    }
 }
@@ -0,0 +1,32 @@
+use super::deadzone_model::DeadzoneModel;
+use super::deadzone_options::{DeadzoneEdition, DeadzoneOptions};
+use super::simulator::calc_dmg_probs;
+use crate::test_vectors::flag_combo_test_vectors;
+
+// exact calculations only, since simulated ones differ from run to run;
+// the defender has armor and a shield die so the editions' rules actually differ
+flag_combo_test_vectors!(
+    "deadzone",
+    calc_dmg_probs {
+        attacker: DeadzoneModel {
+            toxic_dmg: 1,
+            ..DeadzoneModel::new()
+        },
+        defender: DeadzoneModel {
+            armor: 1,
+            num_shield_dice: 1,
+            ..DeadzoneModel::new()
+        },
+        options: DeadzoneOptions {
+            num_simulations: 0,
+            ..DeadzoneOptions::new()
+        },
+    },
+    [
+        attacker.pinned = [false, true],
+        defender.pinned = [false, true],
+        options.aimed = [false, true],
+        options.attacker_can_be_damaged = [false, true],
+        options.edition = [DeadzoneEdition::Third, DeadzoneEdition::Second],
+    ],
+);
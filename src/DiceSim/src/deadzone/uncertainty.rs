@@ -0,0 +1,225 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
+use wasm_bindgen::prelude::*;
+
+use super::deadzone_model::{DeadzoneModel, DeadzoneStat};
+use super::deadzone_options::DeadzoneOptions;
+use super::exact::calc_exact_success_probs;
+use super::roll_params::RollParams;
+use super::simulator::{calc_dmg_probs, tidy_output_probs};
+use super::validation::validate_inputs;
+use crate::common::calc_error::CalcError;
+use crate::common::reduction::{merge_partial_probs, pairwise_sum};
+use crate::common::ts_types::{ToJsMap, RESULT_SCHEMA_VERSION};
+
+#[derive(Tsify, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WeightedStatValue {
+    pub value: i32,
+    pub weight: f64,
+}
+
+// weights are probs, so they have to sum to 1
+#[derive(Tsify, Serialize, Deserialize)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct StatPrior(pub Vec<WeightedStatValue>);
+
+// weights sum to 1
+#[derive(Tsify, Serialize)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+#[serde(rename_all = "camelCase")]
+pub struct StatPosterior {
+    pub result_schema_version: u32,
+    pub items: Vec<WeightedStatValue>,
+}
+
+crate::impl_versioned_items_deserialize!(StatPosterior);
+
+// dice stats considered when no prior is given
+const MIN_PLAUSIBLE_DICE_STAT: i32 = 2;
+const MAX_PLAUSIBLE_DICE_STAT: i32 = 8;
+// rounding slack allowed when checking that a prior's weights sum to 1
+const PRIOR_TOTAL_WEIGHT_TOLERANCE: f64 = 1e-9;
+
+// dmg distribution when one of the defender's stats is unknown, like armor being 1 or 2 depending
+// on an unseen loadout; the defender's current value of that stat is ignored
+#[wasm_bindgen(js_name = "deadzoneCalcDmgProbsWithStatPrior")]
+pub fn deadzone_calc_dmg_probs_with_stat_prior(
+    attacker: &DeadzoneModel,
+    defender: &DeadzoneModel,
+    options: &DeadzoneOptions,
+    stat: DeadzoneStat,
+    prior: StatPrior,
+) -> Result<js_sys::Map, CalcError> {
+    validate_inputs(attacker, defender, options)?;
+    Ok(tidy_output_probs(
+        &calc_dmg_probs_with_stat_prior(attacker, defender, options, stat, &prior)?,
+        options,
+    )
+    .to_js_map())
+}
+
+pub fn calc_dmg_probs_with_stat_prior(
+    attacker: &DeadzoneModel,
+    defender: &DeadzoneModel,
+    options: &DeadzoneOptions,
+    stat: DeadzoneStat,
+    prior: &StatPrior,
+) -> Result<HashMap<i32, f64>, CalcError> {
+    validate_prior(prior)?;
+    // every possible value has to be a valid stat, not just the defender's current one
+    let weighted_probs = prior
+        .0
+        .iter()
+        .filter(|wsv| wsv.weight > 0.0)
+        .map(|weighted_stat_value| {
+            let mut possible_defender = *defender;
+            possible_defender.set_stat(stat, weighted_stat_value.value);
+            validate_inputs(attacker, &possible_defender, options)?;
+            Ok((
+                calc_dmg_probs(attacker, &possible_defender, options),
+                weighted_stat_value.weight,
+            ))
+        })
+        .collect::<Result<Vec<(HashMap<i32, f64>, f64)>, CalcError>>()?;
+    Ok(merge_partial_probs(&weighted_probs))
+}
+
+fn validate_prior(prior: &StatPrior) -> Result<(), CalcError> {
+    let weights: Vec<f64> = prior.0.iter().map(|wsv| wsv.weight).collect();
+    if weights
+        .iter()
+        .any(|weight| !weight.is_finite() || *weight < 0.0)
+        || (pairwise_sum(&weights) - 1.0).abs() > PRIOR_TOTAL_WEIGHT_TOLERANCE
+    {
+        return Err(CalcError::InvalidStat);
+    }
+    Ok(())
+}
+
+// posterior over a model's dice stat after seeing the total successes of several of its rolls,
+// like the opponent's shooting tests over a game; the rest of the model's stats are taken as known.
+// an empty prior means every dice stat from 2+ to 8+ is equally likely beforehand
+#[wasm_bindgen(js_name = "deadzoneInferDiceStat")]
+pub fn deadzone_infer_dice_stat(
+    model: &DeadzoneModel,
+    is_attacker: bool,
+    options: &DeadzoneOptions,
+    observed_successes: Vec<i32>,
+    prior: StatPrior,
+) -> Result<StatPosterior, CalcError> {
+    validate_inputs(model, model, options)?;
+    let prior = if prior.0.is_empty() {
+        let num_dice_stats = MAX_PLAUSIBLE_DICE_STAT - MIN_PLAUSIBLE_DICE_STAT + 1;
+        StatPrior(
+            (MIN_PLAUSIBLE_DICE_STAT..=MAX_PLAUSIBLE_DICE_STAT)
+                .map(|dice_stat| WeightedStatValue {
+                    value: dice_stat,
+                    weight: 1.0 / num_dice_stats as f64,
+                })
+                .collect(),
+        )
+    } else {
+        prior
+    };
+    validate_prior(&prior)?;
+    for wsv in prior.0.iter() {
+        let mut possible_model = *model;
+        possible_model.dice_stat = wsv.value;
+        validate_inputs(&possible_model, &possible_model, options)?;
+    }
+
+    // log space, since many observations make the raw likelihoods underflow
+    let log_weights: Vec<(i32, f64)> = prior
+        .0
+        .iter()
+        .filter(|wsv| wsv.weight > 0.0)
+        .map(|wsv| {
+            let mut possible_model = *model;
+            possible_model.dice_stat = wsv.value;
+            let mut roll_params = RollParams::new(&possible_model, is_attacker, options);
+            roll_params.num_simulations = 0;
+            let success_probs = calc_exact_success_probs(&roll_params);
+            let log_likelihood: f64 = observed_successes
+                .iter()
+                .map(|num_successes| success_probs.get(num_successes).unwrap_or(&0.0).ln())
+                .sum();
+            (wsv.value, wsv.weight.ln() + log_likelihood)
+        })
+        .collect();
+
+    let max_log_weight = log_weights
+        .iter()
+        .map(|(_, log_weight)| *log_weight)
+        .fold(f64::NEG_INFINITY, f64::max);
+    if max_log_weight == f64::NEG_INFINITY {
+        // no candidate could have produced the observations
+        return Ok(StatPosterior {
+            result_schema_version: RESULT_SCHEMA_VERSION,
+            items: vec![],
+        });
+    }
+    let total_weight: f64 = log_weights
+        .iter()
+        .map(|(_, log_weight)| (log_weight - max_log_weight).exp())
+        .sum();
+
+    Ok(StatPosterior {
+        result_schema_version: RESULT_SCHEMA_VERSION,
+        items: log_weights
+            .iter()
+            .map(|(dice_stat, log_weight)| WeightedStatValue {
+                value: *dice_stat,
+                weight: (log_weight - max_log_weight).exp() / total_weight,
+            })
+            .collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn armor_prior(weights: &[(i32, f64)]) -> StatPrior {
+        StatPrior(
+            weights
+                .iter()
+                .map(|(value, weight)| WeightedStatValue {
+                    value: *value,
+                    weight: *weight,
+                })
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn stat_prior_weights_and_values_are_validated() {
+        let attacker = DeadzoneModel::new();
+        let defender = DeadzoneModel::new();
+        let mut options = DeadzoneOptions::new();
+        options.num_simulations = 0;
+        let calc = |prior: &StatPrior| {
+            calc_dmg_probs_with_stat_prior(
+                &attacker,
+                &defender,
+                &options,
+                DeadzoneStat::Armor,
+                prior,
+            )
+        };
+
+        let mixture_probs = calc(&armor_prior(&[(0, 0.25), (1, 0.75)])).unwrap();
+        assert!((mixture_probs.values().sum::<f64>() - 1.0).abs() < 1e-12);
+        for invalid_prior in [
+            armor_prior(&[(0, 1.0), (1, 3.0)]),
+            armor_prior(&[(0, 1.5), (1, -0.5)]),
+            armor_prior(&[(0, f64::NAN), (1, 1.0)]),
+            armor_prior(&[]),
+            armor_prior(&[(-1, 0.5), (1, 0.5)]),
+        ] {
+            assert_eq!(calc(&invalid_prior), Err(CalcError::InvalidStat));
+        }
+    }
+}
@@ -0,0 +1,136 @@
+use wasm_bindgen::prelude::*;
+
+use super::deadzone_model::{DeadzoneModel, DeadzoneStat, FACE_SUCCESS_VALUE_RANGE};
+use super::deadzone_options::DeadzoneOptions;
+use super::exact::check_exact_budget;
+use super::house_rules::pip_success_values;
+use super::roll_params::RollParams;
+use super::simulator::dense_success_counts_len;
+use crate::common::calc_error::CalcError;
+use crate::common::ts_types::{StatRange, StatRanges, RESULT_SCHEMA_VERSION};
+
+// simulating more die rolls than this in one call would hang the UI for a long time
+const MAX_SIMULATED_DIE_ROLLS: i64 = 2_000_000_000;
+
+// the limits validation enforces on model stats, for the UI to build its inputs from; each face's
+// success value has the same limits
+#[wasm_bindgen(js_name = "deadzoneStatRanges")]
+pub fn deadzone_stat_ranges() -> StatRanges {
+    StatRanges {
+        result_schema_version: RESULT_SCHEMA_VERSION,
+        items: DeadzoneStat::ALL
+            .iter()
+            .map(|stat| {
+                let (min, max) = DeadzoneModel::stat_range(*stat);
+                StatRange {
+                    name: DeadzoneModel::stat_js_name(*stat).to_string(),
+                    min,
+                    max,
+                }
+            })
+            .chain(std::iter::once(StatRange {
+                name: "faceSuccessValue".to_string(),
+                min: FACE_SUCCESS_VALUE_RANGE.0,
+                max: FACE_SUCCESS_VALUE_RANGE.1,
+            }))
+            .collect(),
+    }
+}
+
+pub fn validate_inputs(
+    attacker: &DeadzoneModel,
+    defender: &DeadzoneModel,
+    options: &DeadzoneOptions,
+) -> Result<(), CalcError> {
+    validate_model(attacker)?;
+    validate_model(defender)?;
+    validate_options(options)?;
+    // shield and cover dice are always calculated exactly
+    check_exact_budget(&RollParams::for_shields(attacker))?;
+    check_exact_budget(&RollParams::for_shields(defender))?;
+    check_exact_budget(&RollParams::for_cover(options))?;
+
+    // bonus dice from aiming, assists, charging or stances count too, and so do forced rerolls
+    let num_bonus_dice = 1 + std::cmp::max(
+        options.attacker_num_assists as i64
+            + options.charge_bonus_dice as i64
+            + options.attacker_forced_rerolls as i64,
+        options.defender_num_assists as i64
+            + options.defensive_stance_dice as i64
+            + options.defender_forced_rerolls as i64,
+    );
+    for model in [attacker, defender] {
+        let num_die_rolls_per_sim =
+            model.num_dice as i64 + num_bonus_dice + model.num_rerolls as i64;
+        if num_die_rolls_per_sim > i32::MAX as i64 {
+            return Err(CalcError::Overflow);
+        }
+        if num_die_rolls_per_sim * options.num_simulations as i64 > MAX_SIMULATED_DIE_ROLLS {
+            return Err(CalcError::BudgetExceeded);
+        }
+        // the most dice and forced rerolls the model could end up rolling with
+        let mut roll_params = RollParams::new(model, true, options);
+        roll_params.num_dice = (model.num_dice as i64 + num_bonus_dice) as i32;
+        roll_params.num_forced_rerolls = std::cmp::max(
+            options.attacker_forced_rerolls,
+            options.defender_forced_rerolls,
+        );
+        dense_success_counts_len(&roll_params, &pip_success_values(&roll_params))?;
+        // the simulated roll budget is 0 when calculating exactly, so the exact engine has its own;
+        // the exact tails of a hybrid calculation come from the exact engine too
+        if options.num_simulations == 0 || options.exact_tail_mass != 0.0 {
+            check_exact_budget(&roll_params)?;
+        }
+    }
+    Ok(())
+}
+
+pub fn validate_model(model: &DeadzoneModel) -> Result<(), CalcError> {
+    let is_valid = DeadzoneStat::ALL.iter().all(|stat| {
+        let (min, max) = DeadzoneModel::stat_range(*stat);
+        (min..=max).contains(&model.get_stat(*stat))
+    });
+    // face success values can also come in through deserializing, past setFaceSuccessValues
+    let faces_are_valid = model
+        .face_success_values
+        .is_none_or(|values| DeadzoneModel::face_success_values_in_range(&values));
+    if !is_valid || !faces_are_valid {
+        return Err(CalcError::InvalidStat);
+    }
+    // leaves room for the aimed bonus die and rerolls without i32 overflow
+    if model
+        .num_dice
+        .checked_add(model.num_rerolls)
+        .and_then(|n| n.checked_add(1))
+        .is_none()
+    {
+        return Err(CalcError::Overflow);
+    }
+    Ok(())
+}
+
+pub fn validate_options(options: &DeadzoneOptions) -> Result<(), CalcError> {
+    let is_valid = options.num_simulations >= 0
+        && options.num_rounds >= 1
+        && options.exploding_dice_max_levels >= 0
+        && options.attacker_num_assists >= 0
+        && options.defender_num_assists >= 0
+        && options.charge_bonus_dice >= 0
+        && options.charge_pip_bonus >= 0
+        && options.defensive_stance_dice >= 0
+        && options.attacker_forced_rerolls >= 0
+        && options.defender_forced_rerolls >= 0
+        && options.fatigue_dice_penalty >= 0
+        && options.fatigue_pip_penalty >= 0
+        && options.num_cover_dice >= 0
+        && (1..=9).contains(&options.cover_dice_stat)
+        && (0.0..1.0).contains(&options.importance_sampling_success_prob)
+        && (0.0..0.5).contains(&options.exact_tail_mass)
+        && (0.0..1.0).contains(&options.output_min_prob)
+        && options.output_decimals >= 0;
+    if is_valid {
+        Ok(())
+    } else {
+        Err(CalcError::InvalidStat)
+    }
+}
@@ -0,0 +1,116 @@
+use std::cell::RefCell;
+
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
+use wasm_bindgen::prelude::*;
+
+use super::deadzone_model::DeadzoneModel;
+use crate::common::calc_error::CalcError;
+use crate::common::ts_types::RESULT_SCHEMA_VERSION;
+
+// one row per weapon, with its tags comma-separated; the stats are the ones a weapon sets on
+// the model wielding it, and tags are for filtering and display only
+const WEAPON_PRESETS_TSV: &str = include_str!("weapon_presets.tsv");
+
+#[derive(Tsify, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct WeaponPreset {
+    pub name: String,
+    pub faction: String,
+    pub num_dice: i32,
+    pub ap: i32,
+    pub toxic_dmg: i32,
+    pub tags: Vec<String>,
+}
+
+#[derive(Tsify, Serialize)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+#[serde(rename_all = "camelCase")]
+pub struct WeaponPresets {
+    pub result_schema_version: u32,
+    pub items: Vec<WeaponPreset>,
+}
+
+crate::impl_versioned_items_deserialize!(WeaponPresets);
+
+thread_local! {
+    static WEAPON_PRESETS: RefCell<Option<Vec<WeaponPreset>>> = const { RefCell::new(None) };
+}
+
+// weapons of the given faction that have the given tag, where an empty faction or tag matches
+// anything; both are compared ignoring case, and weapons keep the library's order
+#[wasm_bindgen(js_name = "deadzoneQueryWeaponPresets")]
+pub fn deadzone_query_weapon_presets(faction: &str, tag: &str) -> WeaponPresets {
+    let items = with_weapon_presets(|presets| {
+        presets
+            .iter()
+            .filter(|preset| faction.is_empty() || preset.faction.eq_ignore_ascii_case(faction))
+            .filter(|preset| {
+                tag.is_empty()
+                    || preset
+                        .tags
+                        .iter()
+                        .any(|preset_tag| preset_tag.eq_ignore_ascii_case(tag))
+            })
+            .cloned()
+            .collect()
+    });
+    WeaponPresets {
+        result_schema_version: RESULT_SCHEMA_VERSION,
+        items,
+    }
+}
+
+// copy of the model armed with the named weapon
+#[wasm_bindgen(js_name = "deadzoneApplyWeaponPreset")]
+pub fn deadzone_apply_weapon_preset(
+    model: &DeadzoneModel,
+    weapon_name: &str,
+) -> Result<DeadzoneModel, CalcError> {
+    with_weapon_presets(|presets| {
+        let preset = presets
+            .iter()
+            .find(|preset| preset.name.eq_ignore_ascii_case(weapon_name))
+            .ok_or(CalcError::InvalidStat)?;
+        let mut armed_model = *model;
+        armed_model.num_dice = preset.num_dice;
+        armed_model.ap = preset.ap;
+        armed_model.toxic_dmg = preset.toxic_dmg;
+        Ok(armed_model)
+    })
+}
+
+fn with_weapon_presets<T>(f: impl FnOnce(&[WeaponPreset]) -> T) -> T {
+    WEAPON_PRESETS.with(|presets| {
+        f(presets
+            .borrow_mut()
+            .get_or_insert_with(|| parse_weapon_presets(WEAPON_PRESETS_TSV)))
+    })
+}
+
+// rows with missing or non-numeric stats are skipped
+fn parse_weapon_presets(tsv: &str) -> Vec<WeaponPreset> {
+    tsv.lines()
+        .skip(1)
+        .filter_map(|line| {
+            let cells: Vec<&str> = line.split('\t').collect();
+            Some(WeaponPreset {
+                name: cells.first()?.to_string(),
+                faction: cells.get(1)?.to_string(),
+                num_dice: cells.get(2)?.parse().ok()?,
+                ap: cells.get(3)?.parse().ok()?,
+                toxic_dmg: cells.get(4)?.parse().ok()?,
+                tags: cells
+                    .get(5)
+                    .map(|tags| {
+                        tags.split(',')
+                            .map(str::trim)
+                            .filter(|tag| !tag.is_empty())
+                            .map(str::to_string)
+                            .collect()
+                    })
+                    .unwrap_or_default(),
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,253 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
+use wasm_bindgen::prelude::*;
+
+use super::dice_pool_model::DicePoolModel;
+use super::dice_pool_options::DicePoolOptions;
+use crate::common::calc_error::CalcError;
+use crate::common::stat_validation::RangedStats;
+use crate::common::ts_types::{result_schema_v1, ProbMap, RESULT_SCHEMA_VERSION};
+use crate::common::{add_to_map_value, explosion_chain_pmf};
+
+// explosion chains longer than this are vanishingly rare, so we stop tracking them
+const MAX_EXPLOSION_LEVELS: i32 = 20;
+
+#[derive(Tsify, Serialize, Deserialize)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+#[serde(rename_all = "camelCase")]
+pub struct DicePoolResult {
+    #[serde(default = "result_schema_v1")]
+    pub result_schema_version: u32,
+    pub net_success_probs: ProbMap, // attacker successes minus opposing successes
+    pub glitch_prob: f64,           // attacker glitched
+    pub critical_glitch_prob: f64,  // attacker glitched with no successes
+}
+
+#[wasm_bindgen(js_name = "dicePoolCalcOpposedProbs")]
+pub fn dice_pool_calc_opposed_probs(
+    attacker: &DicePoolModel,
+    defender: &DicePoolModel,
+    options: &DicePoolOptions,
+) -> Result<DicePoolResult, CalcError> {
+    attacker.validate_stats()?;
+    defender.validate_stats()?;
+    options.validate_stats()?;
+    if !(0.0..=1.0).contains(&options.glitch_fraction) {
+        return Err(CalcError::InvalidStat);
+    }
+    Ok(calc_opposed_probs(attacker, defender, options))
+}
+
+pub fn calc_opposed_probs(
+    attacker: &DicePoolModel,
+    defender: &DicePoolModel,
+    options: &DicePoolOptions,
+) -> DicePoolResult {
+    let atk_probs = calc_success_and_ones_probs(attacker, options);
+    let def_success_probs =
+        calc_success_probs(&calc_success_and_ones_probs(defender, options), options);
+    let mut net_success_probs = HashMap::<i32, f64>::new();
+    let mut glitch_prob = 0.0;
+    let mut critical_glitch_prob = 0.0;
+
+    for ((atk_successes, atk_ones), atk_prob) in atk_probs.iter() {
+        let atk_net_successes = net_successes(*atk_successes, *atk_ones, options);
+        if is_glitch(*atk_ones, attacker.num_dice, options) {
+            glitch_prob += atk_prob;
+            if atk_net_successes == 0 {
+                critical_glitch_prob += atk_prob;
+            }
+        }
+        for (def_successes, def_prob) in def_success_probs.iter() {
+            add_to_map_value(
+                &mut net_success_probs,
+                &(atk_net_successes - def_successes),
+                atk_prob * def_prob,
+            );
+        }
+    }
+
+    DicePoolResult {
+        result_schema_version: RESULT_SCHEMA_VERSION,
+        net_success_probs: ProbMap(net_success_probs),
+        glitch_prob,
+        critical_glitch_prob,
+    }
+}
+
+fn calc_success_probs(
+    success_and_ones_probs: &HashMap<(i32, i32), f64>,
+    options: &DicePoolOptions,
+) -> HashMap<i32, f64> {
+    let mut success_probs = HashMap::<i32, f64>::new();
+    for ((successes, ones), prob) in success_and_ones_probs.iter() {
+        add_to_map_value(
+            &mut success_probs,
+            &net_successes(*successes, *ones, options),
+            *prob,
+        );
+    }
+    success_probs
+}
+
+fn net_successes(successes: i32, ones: i32, options: &DicePoolOptions) -> i32 {
+    if options.ones_cancel_successes {
+        std::cmp::max(0, successes - ones)
+    } else {
+        successes
+    }
+}
+
+fn is_glitch(ones: i32, num_dice: i32, options: &DicePoolOptions) -> bool {
+    options.glitch_fraction > 0.0 && ones as f64 > options.glitch_fraction * num_dice as f64
+}
+
+// joint distribution of (successes, ones) for the whole pool;
+// only the original dice can show glitching 1s, explosion dice just add successes
+fn calc_success_and_ones_probs(
+    model: &DicePoolModel,
+    options: &DicePoolOptions,
+) -> HashMap<(i32, i32), f64> {
+    let single_die_probs = calc_single_die_probs(model, options);
+    let mut pool_probs = HashMap::from([((0, 0), 1.0)]);
+    for _ in 0..model.num_dice {
+        let mut next_pool_probs = HashMap::<(i32, i32), f64>::new();
+        for ((pool_successes, pool_ones), pool_prob) in pool_probs.iter() {
+            for ((die_successes, die_ones), die_prob) in single_die_probs.iter() {
+                add_to_map_value(
+                    &mut next_pool_probs,
+                    &(pool_successes + die_successes, pool_ones + die_ones),
+                    pool_prob * die_prob,
+                );
+            }
+        }
+        pool_probs = next_pool_probs;
+    }
+    pool_probs
+}
+
+fn calc_single_die_probs(
+    model: &DicePoolModel,
+    options: &DicePoolOptions,
+) -> HashMap<(i32, i32), f64> {
+    let sides = std::cmp::max(1, options.die_sides);
+    let face_prob = 1.0 / sides as f64;
+    let threshold = model.threshold.clamp(1, sides + 1);
+    let num_success_faces = (sides + 1 - threshold) as f64;
+    let explodes = options.exploding && threshold <= sides;
+    let mut die_probs = HashMap::<(i32, i32), f64>::new();
+
+    // face 1 is only a glitching one if it isn't also a success
+    if threshold > 1 {
+        add_to_map_value(&mut die_probs, &(0, 1), face_prob);
+    }
+    let num_plain_fail_faces = std::cmp::max(0, threshold - 2) as f64;
+    add_to_map_value(&mut die_probs, &(0, 0), num_plain_fail_faces * face_prob);
+
+    if !explodes {
+        add_to_map_value(&mut die_probs, &(1, 0), num_success_faces * face_prob);
+        return die_probs;
+    }
+
+    add_to_map_value(
+        &mut die_probs,
+        &(1, 0),
+        (num_success_faces - 1.0) * face_prob,
+    );
+    // rolling the max face gives a success and another roll, which can itself explode;
+    // the chain's final (non-max) roll succeeds with this prob
+    let final_roll_success_prob = if sides > 1 {
+        (num_success_faces - 1.0) / (sides - 1) as f64
+    } else {
+        0.0
+    };
+    for num_explosions in 0..=MAX_EXPLOSION_LEVELS {
+        let chain_prob =
+            face_prob * explosion_chain_pmf(num_explosions, face_prob, MAX_EXPLOSION_LEVELS);
+        let chain_successes = 1 + num_explosions;
+        if num_explosions == MAX_EXPLOSION_LEVELS {
+            add_to_map_value(&mut die_probs, &(chain_successes, 0), chain_prob);
+            continue;
+        }
+        add_to_map_value(
+            &mut die_probs,
+            &(chain_successes, 0),
+            chain_prob * (1.0 - final_roll_success_prob),
+        );
+        add_to_map_value(
+            &mut die_probs,
+            &(chain_successes + 1, 0),
+            chain_prob * final_roll_success_prob,
+        );
+    }
+    die_probs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dice_pool_model(num_dice: i32) -> DicePoolModel {
+        DicePoolModel {
+            num_dice,
+            threshold: 5,
+        }
+    }
+
+    fn assert_prob(probs: &HashMap<i32, f64>, val: i32, expected: f64) {
+        assert!((probs.get(&val).unwrap_or(&0.0) - expected).abs() < 1e-12);
+    }
+
+    // a 6 explodes into another roll, and the chain ends on the first roll that isn't a 6
+    #[test]
+    fn one_exploding_d6_known_values() {
+        let mut options = DicePoolOptions::new();
+        options.exploding = true;
+        let die_probs = calc_single_die_probs(&dice_pool_model(1), &options);
+        assert!((die_probs[&(0, 1)] - 1.0 / 6.0).abs() < 1e-12);
+        assert!((die_probs[&(0, 0)] - 3.0 / 6.0).abs() < 1e-12);
+        // a 5, or a 6 then a 1-4
+        assert!((die_probs[&(1, 0)] - (1.0 / 6.0 + 4.0 / 36.0)).abs() < 1e-12);
+        // a 6 then a 5, or two 6s then a 1-4
+        assert!((die_probs[&(2, 0)] - (1.0 / 36.0 + 4.0 / 216.0)).abs() < 1e-12);
+        let total: f64 = die_probs.values().sum();
+        assert!((total - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn ones_cancel_successes_down_to_zero() {
+        let mut options = DicePoolOptions::new();
+        let success_probs = |options: &DicePoolOptions| {
+            calc_success_probs(
+                &calc_success_and_ones_probs(&dice_pool_model(2), options),
+                options,
+            )
+        };
+        assert_prob(&success_probs(&options), 0, 4.0 / 9.0);
+        options.ones_cancel_successes = true;
+        let cancelled_probs = success_probs(&options);
+        assert_prob(&cancelled_probs, 2, 1.0 / 9.0);
+        assert_prob(&cancelled_probs, 1, 1.0 / 3.0);
+        assert_prob(&cancelled_probs, 0, 5.0 / 9.0);
+    }
+
+    // with 3 dice and a fraction of 0.5, it takes two 1s to glitch, and the third die failing
+    // makes it critical
+    #[test]
+    fn glitch_threshold_and_critical_glitches() {
+        let mut options = DicePoolOptions::new();
+        let result = calc_opposed_probs(&dice_pool_model(3), &dice_pool_model(0), &options);
+        assert!((result.glitch_prob - 16.0 / 216.0).abs() < 1e-12);
+        assert!((result.critical_glitch_prob - 10.0 / 216.0).abs() < 1e-12);
+        assert_prob(&result.net_success_probs.0, 3, 1.0 / 27.0);
+        assert!(!is_glitch(1, 2, &options));
+        assert!(is_glitch(2, 3, &options));
+
+        options.glitch_fraction = 0.0;
+        let result = calc_opposed_probs(&dice_pool_model(3), &dice_pool_model(0), &options);
+        assert_eq!(result.glitch_prob, 0.0);
+        assert_eq!(result.critical_glitch_prob, 0.0);
+    }
+}
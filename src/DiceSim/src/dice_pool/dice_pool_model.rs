@@ -0,0 +1,42 @@
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+use crate::common::stat_validation::{RangedStats, MAX_COUNT_STAT};
+use crate::common::ts_types::StatRanges;
+
+#[wasm_bindgen]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DicePoolModel {
+    #[wasm_bindgen(js_name = numDice)]
+    pub num_dice: i32,
+    pub threshold: i32, // die results at or above this are successes
+}
+
+#[wasm_bindgen]
+impl DicePoolModel {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> DicePoolModel {
+        DicePoolModel {
+            num_dice: 6,
+            threshold: 5,
+        }
+    }
+}
+
+// thresholds above the die's sides just never succeed
+impl RangedStats for DicePoolModel {
+    const STAT_RANGES: &'static [(&'static str, i32, i32)] = &[
+        ("numDice", 0, MAX_COUNT_STAT),
+        ("threshold", 1, MAX_COUNT_STAT),
+    ];
+
+    fn stat_values(&self) -> Vec<i32> {
+        vec![self.num_dice, self.threshold]
+    }
+}
+
+#[wasm_bindgen(js_name = "dicePoolStatRanges")]
+pub fn dice_pool_stat_ranges() -> StatRanges {
+    StatRanges::from_ranges(DicePoolModel::STAT_RANGES)
+}
@@ -0,0 +1,39 @@
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+use crate::common::stat_validation::{RangedStats, MAX_COUNT_STAT};
+
+#[wasm_bindgen]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DicePoolOptions {
+    #[wasm_bindgen(js_name = dieSides)]
+    pub die_sides: i32,
+    pub exploding: bool, // max result is a success and rolls another die
+    #[wasm_bindgen(js_name = onesCancelSuccesses)]
+    pub ones_cancel_successes: bool, // botch-style rule where each 1 removes a success
+    #[wasm_bindgen(js_name = glitchFraction)]
+    pub glitch_fraction: f64, // glitch if more than this fraction of dice are 1s; 0 disables glitches
+}
+
+#[wasm_bindgen]
+impl DicePoolOptions {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> DicePoolOptions {
+        DicePoolOptions {
+            die_sides: 6,
+            exploding: false,
+            ones_cancel_successes: false,
+            glitch_fraction: 0.5,
+        }
+    }
+}
+
+// a die needs at least two sides for the 1s and the max result to differ
+impl RangedStats for DicePoolOptions {
+    const STAT_RANGES: &'static [(&'static str, i32, i32)] = &[("dieSides", 2, MAX_COUNT_STAT)];
+
+    fn stat_values(&self) -> Vec<i32> {
+        vec![self.die_sides]
+    }
+}
@@ -0,0 +1,8 @@
+mod calculator;
+mod dice_pool_model;
+mod dice_pool_options;
+#[cfg(feature = "test-vectors")]
+mod test_vectors;
+
+#[cfg(feature = "test-vectors")]
+pub(crate) use test_vectors::test_vectors;
@@ -0,0 +1,20 @@
+use super::calculator::calc_opposed_probs;
+use super::dice_pool_model::DicePoolModel;
+use super::dice_pool_options::DicePoolOptions;
+use crate::test_vectors::flag_combo_test_vectors;
+
+flag_combo_test_vectors!(
+    "dicePool",
+    calc_opposed_probs {
+        attacker: DicePoolModel::new(),
+        defender: DicePoolModel {
+            num_dice: 4,
+            ..DicePoolModel::new()
+        },
+        options: DicePoolOptions::new(),
+    },
+    [
+        options.exploding = [false, true],
+        options.ones_cancel_successes = [false, true]
+    ],
+);
@@ -0,0 +1,214 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
+use wasm_bindgen::prelude::*;
+
+use super::infinity_model::InfinityModel;
+use crate::common::calc_error::CalcError;
+use crate::common::stat_validation::RangedStats;
+use crate::common::ts_types::{result_schema_v1, ProbMap, RESULT_SCHEMA_VERSION};
+use crate::common::{add_to_map_value, binomial_pmf};
+
+const DIE_SIDES: i32 = 20;
+const SAVES_PER_CRIT: i32 = 2;
+
+#[derive(Tsify, Serialize, Deserialize)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+#[serde(rename_all = "camelCase")]
+pub struct InfinityF2fResult {
+    #[serde(default = "result_schema_v1")]
+    pub result_schema_version: u32,
+    pub reactive_wound_probs: ProbMap, // wounds the active model deals to the reactive model
+    pub active_wound_probs: ProbMap,   // wounds the reactive model deals back
+}
+
+#[wasm_bindgen(js_name = "infinityCalcF2fWoundProbs")]
+pub fn infinity_calc_f2f_wound_probs(
+    active: &InfinityModel,
+    reactive: &InfinityModel,
+) -> Result<InfinityF2fResult, CalcError> {
+    active.validate_stats()?;
+    reactive.validate_stats()?;
+    Ok(calc_f2f_wound_probs(active, reactive))
+}
+
+pub fn calc_f2f_wound_probs(active: &InfinityModel, reactive: &InfinityModel) -> InfinityF2fResult {
+    InfinityF2fResult {
+        result_schema_version: RESULT_SCHEMA_VERSION,
+        reactive_wound_probs: ProbMap(calc_wound_probs(active, reactive)),
+        active_wound_probs: ProbMap(calc_wound_probs(reactive, active)),
+    }
+}
+
+// wounds that shooter deals to target when both roll face-to-face;
+// each uncanceled hit forces one ARM save, and each uncanceled crit forces an extra one
+pub fn calc_wound_probs(shooter: &InfinityModel, target: &InfinityModel) -> HashMap<i32, f64> {
+    let fail_save_prob = fail_save_prob(shooter.damage, target.arm);
+    let mut wound_probs = HashMap::<i32, f64>::new();
+
+    for ((hits, crits), prob) in calc_uncanceled_hit_probs(shooter, target).iter() {
+        let num_saves = hits + SAVES_PER_CRIT * crits;
+        if num_saves == 0 {
+            add_to_map_value(&mut wound_probs, &0, *prob);
+            continue;
+        }
+        for num_wounds in 0..=num_saves {
+            add_to_map_value(
+                &mut wound_probs,
+                &num_wounds,
+                prob * binomial_pmf(num_saves, num_wounds, fail_save_prob),
+            );
+        }
+    }
+    wound_probs
+}
+
+// joint distribution of shooter's (uncanceled non-crit hits, uncanceled crits);
+// the opponent's best success cancels all of shooter's successes that are lower or equal,
+// and an opponent crit cancels everything
+fn calc_uncanceled_hit_probs(
+    shooter: &InfinityModel,
+    opponent: &InfinityModel,
+) -> HashMap<(i32, i32), f64> {
+    let shooter_attribute = shooter.attribute.clamp(0, DIE_SIDES);
+    let mut hit_probs = HashMap::<(i32, i32), f64>::new();
+
+    for (opponent_best, opponent_prob) in calc_best_success_probs(opponent).iter() {
+        let (hit_prob, crit_prob) = match opponent_best {
+            BestSuccess::Crit => (0.0, 0.0),
+            BestSuccess::Value(best_value) => (
+                std::cmp::max(0, shooter_attribute - 1 - best_value) as f64 / DIE_SIDES as f64,
+                if shooter_attribute > 0 {
+                    1.0 / DIE_SIDES as f64
+                } else {
+                    0.0
+                },
+            ),
+        };
+
+        let mut shooter_probs = HashMap::from([((0, 0), *opponent_prob)]);
+        for _ in 0..shooter.burst {
+            let mut next_shooter_probs = HashMap::<(i32, i32), f64>::new();
+            for ((hits, crits), prob) in shooter_probs.iter() {
+                add_to_map_value(
+                    &mut next_shooter_probs,
+                    &(hits + 1, *crits),
+                    prob * hit_prob,
+                );
+                add_to_map_value(
+                    &mut next_shooter_probs,
+                    &(*hits, crits + 1),
+                    prob * crit_prob,
+                );
+                add_to_map_value(
+                    &mut next_shooter_probs,
+                    &(*hits, *crits),
+                    prob * (1.0 - hit_prob - crit_prob),
+                );
+            }
+            shooter_probs = next_shooter_probs;
+        }
+        for (hits_and_crits, prob) in shooter_probs.iter() {
+            add_to_map_value(&mut hit_probs, hits_and_crits, *prob);
+        }
+    }
+    hit_probs
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+enum BestSuccess {
+    Value(i32), // 0 means no successes
+    Crit,
+}
+
+fn calc_best_success_probs(model: &InfinityModel) -> HashMap<BestSuccess, f64> {
+    let attribute = model.attribute.clamp(0, DIE_SIDES);
+    let mut best_probs = HashMap::<BestSuccess, f64>::new();
+    if model.burst <= 0 || attribute == 0 {
+        best_probs.insert(BestSuccess::Value(0), 1.0);
+        return best_probs;
+    }
+
+    // prob that no die is a crit and every die is a failure or at most this value
+    let prob_all_at_most =
+        |value: i32| ((DIE_SIDES - attribute + value) as f64 / DIE_SIDES as f64).powi(model.burst);
+    let mut prev_cumulative_prob = 0.0;
+    for value in 0..attribute {
+        let cumulative_prob = prob_all_at_most(value);
+        best_probs.insert(
+            BestSuccess::Value(value),
+            cumulative_prob - prev_cumulative_prob,
+        );
+        prev_cumulative_prob = cumulative_prob;
+    }
+    best_probs.insert(BestSuccess::Crit, 1.0 - prev_cumulative_prob);
+    best_probs
+}
+
+// save succeeds if d20 + ARM beats the damage
+fn fail_save_prob(damage: i32, arm: i32) -> f64 {
+    let max_failing_roll = (damage - arm).clamp(0, DIE_SIDES);
+    max_failing_roll as f64 / DIE_SIDES as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn infinity_model(burst: i32, attribute: i32) -> InfinityModel {
+        InfinityModel {
+            burst,
+            attribute,
+            damage: 13,
+            arm: 1,
+        }
+    }
+
+    // with nothing to beat, attribute 12 hits on 1-11 and crits on 12, and the save fails on 1-12
+    #[test]
+    fn unopposed_burst_one_known_values() {
+        let wound_probs = calc_wound_probs(&infinity_model(1, 12), &infinity_model(0, 12));
+        let fail_prob = 12.0 / 20.0;
+        assert_eq!(fail_save_prob(13, 1), fail_prob);
+        assert!((wound_probs[&2] - (1.0 / 20.0) * fail_prob * fail_prob).abs() < 1e-12);
+        assert!(
+            (wound_probs[&1]
+                - (11.0 / 20.0) * fail_prob
+                - (1.0 / 20.0) * 2.0 * fail_prob * (1.0 - fail_prob))
+                .abs()
+                < 1e-12
+        );
+    }
+
+    // up to 45 saves, past what n_choose_k's table has
+    #[test]
+    fn big_burst_needs_many_saves() {
+        let wound_probs = calc_wound_probs(&infinity_model(15, 20), &infinity_model(0, 12));
+        let total: f64 = wound_probs.values().sum();
+        assert!((total - 1.0).abs() < 1e-9);
+        assert!(wound_probs.keys().max() > Some(&29));
+        assert_eq!(
+            infinity_model(0, 21).validate_stats(),
+            Err(CalcError::InvalidStat)
+        );
+    }
+
+    #[test]
+    fn best_success_probs_include_the_crit() {
+        let best_probs = calc_best_success_probs(&infinity_model(1, 12));
+        assert!((best_probs[&BestSuccess::Value(0)] - 8.0 / 20.0).abs() < 1e-12);
+        assert!((best_probs[&BestSuccess::Value(11)] - 1.0 / 20.0).abs() < 1e-12);
+        assert!((best_probs[&BestSuccess::Crit] - 1.0 / 20.0).abs() < 1e-12);
+        let total: f64 = best_probs.values().sum();
+        assert!((total - 1.0).abs() < 1e-12);
+    }
+
+    // the opponent's crit comes up 1 time in 20 and cancels the shooter's hits and crits alike
+    #[test]
+    fn opponent_crit_cancels_everything() {
+        let hit_probs = calc_uncanceled_hit_probs(&infinity_model(1, 20), &infinity_model(1, 1));
+        assert!((hit_probs[&(0, 0)] - 1.0 / 20.0).abs() < 1e-12);
+        assert!((hit_probs[&(1, 0)] - (19.0 / 20.0) * (19.0 / 20.0)).abs() < 1e-12);
+    }
+}
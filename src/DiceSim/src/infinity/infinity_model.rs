@@ -0,0 +1,47 @@
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+use crate::common::stat_validation::{RangedStats, MAX_COUNT_STAT};
+use crate::common::ts_types::StatRanges;
+
+#[wasm_bindgen]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InfinityModel {
+    pub burst: i32,
+    pub attribute: i32, // BS/CC/PH after modifiers; roll at or under to succeed, exactly equal is a crit
+    pub damage: i32,
+    pub arm: i32,
+}
+
+#[wasm_bindgen]
+impl InfinityModel {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> InfinityModel {
+        InfinityModel {
+            burst: 1,
+            attribute: 12,
+            damage: 13,
+            arm: 1,
+        }
+    }
+}
+
+// attributes past 20 succeed like 20 does, on every face
+impl RangedStats for InfinityModel {
+    const STAT_RANGES: &'static [(&'static str, i32, i32)] = &[
+        ("burst", 0, MAX_COUNT_STAT),
+        ("attribute", 0, 20),
+        ("damage", 0, MAX_COUNT_STAT),
+        ("arm", 0, MAX_COUNT_STAT),
+    ];
+
+    fn stat_values(&self) -> Vec<i32> {
+        vec![self.burst, self.attribute, self.damage, self.arm]
+    }
+}
+
+#[wasm_bindgen(js_name = "infinityStatRanges")]
+pub fn infinity_stat_ranges() -> StatRanges {
+    StatRanges::from_ranges(InfinityModel::STAT_RANGES)
+}
@@ -0,0 +1,16 @@
+use super::calculator::calc_f2f_wound_probs;
+use super::infinity_model::InfinityModel;
+use crate::test_vectors::flag_combo_test_vectors;
+
+// no rule flags, so just a face-to-face roll where both sides can wound
+flag_combo_test_vectors!(
+    "infinity",
+    calc_f2f_wound_probs {
+        active: InfinityModel {
+            burst: 3,
+            ..InfinityModel::new()
+        },
+        reactive: InfinityModel::new(),
+    },
+    [],
+);
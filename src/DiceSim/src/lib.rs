@@ -1,5 +1,16 @@
+mod block_dice;
+mod bolt_action;
 mod common;
 mod deadzone;
+mod dice_pool;
+mod infinity;
+mod opr;
+mod self_test;
+mod warcry;
+mod xwing;
+
+#[cfg(feature = "test-vectors")]
+pub mod test_vectors;
 
 use wasm_bindgen::prelude::*;
 
@@ -0,0 +1,144 @@
+use std::collections::HashMap;
+
+use wasm_bindgen::prelude::*;
+
+use super::opr_model::OprModel;
+use super::opr_options::OprOptions;
+use crate::common::calc_error::CalcError;
+use crate::common::stat_validation::RangedStats;
+use crate::common::ts_types::ToJsMap;
+use crate::common::{add_to_map_value, binomial_pmf, calc_multi_round_damage};
+
+const DIE_SIDES: f64 = 6.0;
+const RENDING_AP: i32 = 4;
+const REGENERATION_PROB: f64 = 2.0 / DIE_SIDES;
+
+#[wasm_bindgen(js_name = "oprCalcWoundProbs")]
+pub fn opr_calc_wound_probs(
+    attacker: &OprModel,
+    defender: &OprModel,
+    options: &OprOptions,
+) -> Result<js_sys::Map, CalcError> {
+    attacker.validate_stats()?;
+    defender.validate_stats()?;
+    Ok(calc_wound_probs(attacker, defender, options).to_js_map())
+}
+
+// attack dice are independent, so find the wounds from one attack die and convolve
+pub fn calc_wound_probs(
+    attacker: &OprModel,
+    defender: &OprModel,
+    options: &OprOptions,
+) -> HashMap<i32, f64> {
+    if attacker.attacks <= 0 {
+        return HashMap::from([(0, 1.0)]);
+    }
+
+    let normal_hit_prob = (6 - d6_target(attacker.quality)) as f64 / DIE_SIDES;
+    let six_prob = 1.0 / DIE_SIDES;
+    let blast = std::cmp::max(1, attacker.blast);
+    let six_ap = if attacker.rending {
+        std::cmp::max(attacker.ap, RENDING_AP)
+    } else {
+        attacker.ap
+    };
+    let six_hits = if attacker.furious { blast * 2 } else { blast };
+
+    let mut single_die_wound_probs = HashMap::<i32, f64>::new();
+    add_to_map_value(
+        &mut single_die_wound_probs,
+        &0,
+        1.0 - normal_hit_prob - six_prob,
+    );
+    for (num_hits, ap, hit_prob) in [
+        (blast, attacker.ap, normal_hit_prob),
+        (six_hits, six_ap, six_prob),
+    ] {
+        let wound_prob = calc_wound_prob_per_hit(ap, defender, options);
+        for num_wounds in 0..=num_hits {
+            add_to_map_value(
+                &mut single_die_wound_probs,
+                &(num_wounds * std::cmp::max(1, attacker.deadly)),
+                hit_prob * binomial_pmf(num_hits, num_wounds, wound_prob),
+            );
+        }
+    }
+
+    calc_multi_round_damage(&single_die_wound_probs, attacker.attacks)
+}
+
+fn calc_wound_prob_per_hit(ap: i32, defender: &OprModel, options: &OprOptions) -> f64 {
+    let cover_bonus = if options.in_cover { 1 } else { 0 };
+    let save_prob = (7 - d6_target(defender.defense + ap - cover_bonus)) as f64 / DIE_SIDES;
+    let unsaved_prob = 1.0 - save_prob;
+    // Deadly multiplies wounds after Regeneration, so regeneration rolls once per wound
+    if defender.regeneration {
+        unsaved_prob * (1.0 - REGENERATION_PROB)
+    } else {
+        unsaved_prob
+    }
+}
+
+// unmodified 1s always fail and unmodified 6s always succeed
+fn d6_target(modified_target: i32) -> i32 {
+    modified_target.clamp(2, 6)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn one_attack_known_value() {
+        let mut attacker = OprModel::new();
+        attacker.attacks = 1;
+        // hits on 4+ and the 4+ save fails half the time
+        let wound_probs = calc_wound_probs(&attacker, &OprModel::new(), &OprOptions::new());
+        assert!((wound_probs[&1] - 0.25).abs() < 1e-12);
+        assert!((wound_probs[&0] - 0.75).abs() < 1e-12);
+    }
+
+    // a furious 6 with the biggest blast is 40 hits, past what n_choose_k's table has
+    #[test]
+    fn big_blast_hits() {
+        let mut attacker = OprModel::new();
+        attacker.attacks = 1;
+        attacker.furious = true;
+        attacker.blast = 20;
+        attacker.validate_stats().unwrap();
+        let wound_probs = calc_wound_probs(&attacker, &OprModel::new(), &OprOptions::new());
+        assert!(wound_probs.contains_key(&40));
+        let total: f64 = wound_probs.values().sum();
+        assert!((total - 1.0).abs() < 1e-12);
+
+        attacker.blast = 21;
+        assert_eq!(attacker.validate_stats(), Err(CalcError::InvalidStat));
+    }
+
+    // a 6 to hit becomes two rending hits, which only a natural 6 saves, and each wound is doubled
+    #[test]
+    fn furious_rending_deadly_six() {
+        let mut attacker = OprModel::new();
+        attacker.attacks = 1;
+        attacker.furious = true;
+        attacker.rending = true;
+        attacker.deadly = 2;
+        let wound_probs = calc_wound_probs(&attacker, &OprModel::new(), &OprOptions::new());
+        assert!((wound_probs[&4] - (1.0 / 6.0) * (5.0f64 / 6.0).powi(2)).abs() < 1e-12);
+        assert!(!wound_probs.contains_key(&3));
+        let total: f64 = wound_probs.values().sum();
+        assert!((total - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn cover_and_regeneration_reduce_the_wound_prob() {
+        let mut defender = OprModel::new();
+        defender.regeneration = true;
+        let options = OprOptions { in_cover: true };
+        // cover makes it a 3+ save, then regeneration ignores a wound on 5+
+        assert!(
+            (calc_wound_prob_per_hit(0, &defender, &options) - (2.0 / 6.0) * (4.0 / 6.0)).abs()
+                < 1e-12
+        );
+    }
+}
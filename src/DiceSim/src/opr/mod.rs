@@ -0,0 +1,8 @@
+mod calculator;
+mod opr_model;
+mod opr_options;
+#[cfg(feature = "test-vectors")]
+mod test_vectors;
+
+#[cfg(feature = "test-vectors")]
+pub(crate) use test_vectors::test_vectors;
@@ -0,0 +1,69 @@
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+use crate::common::stat_validation::{RangedStats, MAX_COUNT_STAT};
+use crate::common::ts_types::StatRanges;
+
+#[wasm_bindgen]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OprModel {
+    pub attacks: i32,
+    pub quality: i32, // to-hit roll needed
+    pub ap: i32,
+    pub blast: i32,         // each hit becomes this many hits; 1 means no Blast
+    pub deadly: i32,        // each unsaved wound becomes this many wounds; 1 means no Deadly
+    pub rending: bool,      // unmodified 6s to hit get AP(4)
+    pub furious: bool,      // unmodified 6s to hit deal one extra hit
+    pub defense: i32,       // save roll needed
+    pub regeneration: bool, // 5+ to ignore each wound
+}
+
+#[wasm_bindgen]
+impl OprModel {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> OprModel {
+        OprModel {
+            attacks: 2,
+            quality: 4,
+            ap: 0,
+            blast: 1,
+            deadly: 1,
+            rending: false,
+            furious: false,
+            defense: 4,
+            regeneration: false,
+        }
+    }
+}
+
+const MAX_MULTIPLIER: i32 = 20;
+
+// quality and defense are d6 targets, and 1s always fail while 6s always succeed; blast and deadly
+// multiply every hit and wound, so they get a tighter cap than attacks
+impl RangedStats for OprModel {
+    const STAT_RANGES: &'static [(&'static str, i32, i32)] = &[
+        ("attacks", 0, MAX_COUNT_STAT),
+        ("quality", 2, 6),
+        ("ap", 0, MAX_COUNT_STAT),
+        ("blast", 1, MAX_MULTIPLIER),
+        ("deadly", 1, MAX_MULTIPLIER),
+        ("defense", 2, 6),
+    ];
+
+    fn stat_values(&self) -> Vec<i32> {
+        vec![
+            self.attacks,
+            self.quality,
+            self.ap,
+            self.blast,
+            self.deadly,
+            self.defense,
+        ]
+    }
+}
+
+#[wasm_bindgen(js_name = "oprStatRanges")]
+pub fn opr_stat_ranges() -> StatRanges {
+    StatRanges::from_ranges(OprModel::STAT_RANGES)
+}
@@ -0,0 +1,18 @@
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OprOptions {
+    #[wasm_bindgen(js_name = inCover)]
+    pub in_cover: bool, // +1 to defender's save rolls
+}
+
+#[wasm_bindgen]
+impl OprOptions {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> OprOptions {
+        OprOptions { in_cover: false }
+    }
+}
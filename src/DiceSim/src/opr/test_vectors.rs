@@ -0,0 +1,19 @@
+use super::calculator::calc_wound_probs;
+use super::opr_model::OprModel;
+use super::opr_options::OprOptions;
+use crate::test_vectors::flag_combo_test_vectors;
+
+flag_combo_test_vectors!(
+    "opr",
+    calc_wound_probs {
+        attacker: OprModel::new(),
+        defender: OprModel::new(),
+        options: OprOptions::new(),
+    },
+    [
+        attacker.rending = [false, true],
+        attacker.furious = [false, true],
+        defender.regeneration = [false, true],
+        options.in_cover = [false, true],
+    ],
+);
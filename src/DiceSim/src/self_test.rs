@@ -0,0 +1,91 @@
+// known-answer checks that run inside whatever build is deployed, for when results look suspicious;
+// simulated checks use a fixed seed, so a build either always passes them or never does.
+// each engine builds its own checks since its calculator is private to it
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
+use wasm_bindgen::prelude::*;
+
+use crate::common::ts_types::{result_schema_v1, RESULT_SCHEMA_VERSION};
+use crate::common::{binomial_pmf, n_choose_k};
+
+// for seeding rngs of simulated checks
+pub(crate) const SELF_TEST_SEED: u64 = 20211021;
+
+#[derive(Tsify, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SelfTestCheck {
+    pub name: String,
+    pub passed: bool,
+    pub details: String, // what was compared, and how far off it was
+}
+
+#[derive(Tsify, Serialize, Deserialize)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+#[serde(rename_all = "camelCase")]
+pub struct SelfTestReport {
+    #[serde(default = "result_schema_v1")]
+    pub result_schema_version: u32,
+    pub all_passed: bool,
+    pub checks: Vec<SelfTestCheck>,
+}
+
+// house-rule hooks change what dice count as, so clear them before trusting a failure
+#[wasm_bindgen(js_name = "selfTest")]
+pub fn self_test() -> SelfTestReport {
+    let mut checks = vec![check_binomial_identities()];
+    checks.extend(crate::deadzone::self_test_checks());
+    SelfTestReport {
+        result_schema_version: RESULT_SCHEMA_VERSION,
+        all_passed: checks.iter().all(|check| check.passed),
+        checks,
+    }
+}
+
+// passes when the biggest bucket-by-bucket difference is within tolerance
+pub(crate) fn check_probs_match(
+    name: &str,
+    expected_probs: &HashMap<i32, f64>,
+    actual_probs: &HashMap<i32, f64>,
+    tolerance: f64,
+) -> SelfTestCheck {
+    let max_diff = expected_probs
+        .keys()
+        .chain(actual_probs.keys())
+        .map(|val| {
+            (expected_probs.get(val).unwrap_or(&0.0) - actual_probs.get(val).unwrap_or(&0.0)).abs()
+        })
+        .fold(0.0, f64::max);
+    SelfTestCheck {
+        name: name.to_string(),
+        passed: max_diff <= tolerance,
+        details: format!("max prob diff {max_diff:e}, tolerance {tolerance:e}"),
+    }
+}
+
+// pmf sums to 1 with mean n*p, and Pascal's rule holds for the coefficients
+fn check_binomial_identities() -> SelfTestCheck {
+    let (num_trials, prob_success) = (12, 0.375);
+    let pmf: Vec<f64> = (0..=num_trials)
+        .map(|k| binomial_pmf(num_trials, k, prob_success))
+        .collect();
+    let total: f64 = pmf.iter().sum();
+    let mean: f64 = pmf
+        .iter()
+        .enumerate()
+        .map(|(k, prob)| k as f64 * prob)
+        .sum();
+    let pascal_holds = (1..20).all(|n| {
+        (1..n).all(|k| n_choose_k(n, k) == n_choose_k(n - 1, k - 1) + n_choose_k(n - 1, k))
+    });
+    let total_diff = (total - 1.0).abs();
+    let mean_diff = (mean - num_trials as f64 * prob_success).abs();
+    SelfTestCheck {
+        name: "common.binomialIdentities".to_string(),
+        passed: total_diff < 1e-12 && mean_diff < 1e-12 && pascal_holds,
+        details: format!(
+            "total off by {total_diff:e}, mean off by {mean_diff:e}, pascal's rule holds: {pascal_holds}"
+        ),
+    }
+}
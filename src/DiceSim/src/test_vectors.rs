@@ -0,0 +1,101 @@
+// canonical input/output fixtures from every engine's exact calculations, over every combination
+// of its rule flags, so the JS app and this crate can validate against the same numbers;
+// each engine registers its own vectors since its calculator is private to it
+use serde_json::{json, Value};
+
+use crate::common::ts_types::RESULT_SCHEMA_VERSION;
+
+pub fn generate_test_vectors() -> Value {
+    let mut vectors = Vec::new();
+    vectors.extend(crate::block_dice::test_vectors());
+    vectors.extend(crate::bolt_action::test_vectors());
+    vectors.extend(crate::deadzone::test_vectors());
+    vectors.extend(crate::dice_pool::test_vectors());
+    vectors.extend(crate::infinity::test_vectors());
+    vectors.extend(crate::opr::test_vectors());
+    vectors.extend(crate::warcry::test_vectors());
+    vectors.extend(crate::xwing::test_vectors());
+    json!({
+        "resultSchemaVersion": RESULT_SCHEMA_VERSION,
+        "vectors": vectors,
+    })
+}
+
+// one vector per combination of an engine's rule flags' settings, starting with every flag at its
+// first setting and varying the first flag fastest; case sets up the combination given each flag's
+// setting index and returns its inputs, which should serialize the way JS passes them in, along
+// with its output
+pub(crate) fn flag_combo_vectors<Inputs: serde::Serialize, Output: serde::Serialize>(
+    engine: &str,
+    num_settings: &[usize],
+    case: impl Fn(&[usize]) -> (Inputs, Output),
+) -> Vec<Value> {
+    (0..num_settings.iter().product())
+        .map(|combo: usize| {
+            let settings: Vec<usize> = num_settings
+                .iter()
+                .scan(combo, |rest, num| {
+                    let setting = *rest % num;
+                    *rest /= num;
+                    Some(setting)
+                })
+                .collect();
+            let (inputs, output) = case(&settings);
+            test_vector(engine, serde_json::to_value(inputs).unwrap(), output)
+        })
+        .collect()
+}
+
+// registers an engine with flag_combo_vectors by defining its test_vectors fn: each input starts
+// from its expression, each listed field takes each of its settings in turn, and the inputs are
+// serialized under their own names and passed by reference to the calculation
+macro_rules! flag_combo_test_vectors {
+    (
+        $engine:literal,
+        $calc:path { $($input:ident: $init:expr),+ $(,)? },
+        [$($owner:ident.$field:ident = [$($setting:expr),+]),* $(,)?] $(,)?
+    ) => {
+        pub fn test_vectors() -> Vec<serde_json::Value> {
+            $crate::test_vectors::flag_combo_vectors(
+                $engine,
+                &[$([$($setting),+].len()),*],
+                |settings| {
+                    $(#[allow(unused_mut)] let mut $input = $init;)+
+                    #[allow(unused_mut, unused_variables)] // with no flags to set
+                    let mut settings = settings.iter();
+                    $($owner.$field = [$($setting),+][*settings.next().unwrap()];)*
+                    (
+                        serde_json::json!({ $(stringify!($input): $input),+ }),
+                        $calc($(&$input),+),
+                    )
+                },
+            )
+        }
+    };
+}
+pub(crate) use flag_combo_test_vectors;
+
+// output goes through serde_json's sorted maps, and its probs are rounded since summing them in
+// hash map order can change the last bits, so vectors are the same from run to run
+fn test_vector(engine: &str, inputs: Value, output: impl serde::Serialize) -> Value {
+    let mut output = serde_json::to_value(output).unwrap();
+    round_floats(&mut output);
+    json!({
+        "engine": engine,
+        "inputs": inputs,
+        "output": output,
+    })
+}
+
+// to 12 significant digits, so fixtures should be compared with a relative tolerance of about 1e-11
+fn round_floats(value: &mut Value) {
+    match value {
+        Value::Number(num) if num.is_f64() => {
+            let rounded: f64 = format!("{:.11e}", num.as_f64().unwrap()).parse().unwrap();
+            *value = json!(rounded);
+        }
+        Value::Array(items) => items.iter_mut().for_each(round_floats),
+        Value::Object(fields) => fields.values_mut().for_each(round_floats),
+        _ => {}
+    }
+}
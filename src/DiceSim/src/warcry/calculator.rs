@@ -0,0 +1,88 @@
+use std::collections::HashMap;
+
+use wasm_bindgen::prelude::*;
+
+use super::warcry_model::WarcryModel;
+use super::warcry_options::WarcryOptions;
+use crate::common::calc_error::CalcError;
+use crate::common::stat_validation::RangedStats;
+use crate::common::ts_types::ToJsMap;
+use crate::common::{add_to_map_value, calc_multi_round_damage};
+
+const CRIT_PIP: i32 = 6;
+const DIE_SIDES: f64 = 6.0;
+
+#[wasm_bindgen(js_name = "warcryCalcDmgProbs")]
+pub fn warcry_calc_dmg_probs(
+    attacker: &WarcryModel,
+    defender: &WarcryModel,
+    options: &WarcryOptions,
+) -> Result<js_sys::Map, CalcError> {
+    attacker.validate_stats()?;
+    defender.validate_stats()?;
+    options.validate_stats()?;
+    Ok(calc_dmg_probs(attacker, defender, options).to_js_map())
+}
+
+// every attack die is independent, so the activation's damage is just the
+// single-die damage distribution convolved with itself once per die
+pub fn calc_dmg_probs(
+    attacker: &WarcryModel,
+    defender: &WarcryModel,
+    options: &WarcryOptions,
+) -> HashMap<i32, f64> {
+    let num_dice = attacker.attacks * options.num_attack_actions;
+    if num_dice <= 0 {
+        return HashMap::from([(0, 1.0)]);
+    }
+
+    let hit_pip = calc_hit_pip(attacker.strength, defender.toughness);
+    let hit_prob = (CRIT_PIP - hit_pip) as f64 / DIE_SIDES;
+    let crit_prob = 1.0 / DIE_SIDES;
+    let mut single_die_dmg_probs = HashMap::<i32, f64>::new();
+    add_to_map_value(&mut single_die_dmg_probs, &0, 1.0 - hit_prob - crit_prob);
+    add_to_map_value(&mut single_die_dmg_probs, &attacker.hit_dmg, hit_prob);
+    add_to_map_value(&mut single_die_dmg_probs, &attacker.crit_dmg, crit_prob);
+
+    calc_multi_round_damage(&single_die_dmg_probs, num_dice)
+}
+
+// strength above toughness hits on 3+, equal on 4+, below on 5+; a 6 is always a crit
+fn calc_hit_pip(strength: i32, toughness: i32) -> i32 {
+    match strength.cmp(&toughness) {
+        std::cmp::Ordering::Greater => 3,
+        std::cmp::Ordering::Equal => 4,
+        std::cmp::Ordering::Less => 5,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn one_die_known_values() {
+        let mut attacker = WarcryModel::new();
+        attacker.attacks = 1;
+        let defender = WarcryModel::new();
+        // equal strength and toughness hits on 4 or 5, and crits on 6
+        let dmg_probs = calc_dmg_probs(&attacker, &defender, &WarcryOptions::new());
+        assert!((dmg_probs[&0] - 0.5).abs() < 1e-12);
+        assert!((dmg_probs[&1] - 1.0 / 3.0).abs() < 1e-12);
+        assert!((dmg_probs[&3] - 1.0 / 6.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn second_attack_action_doubles_the_dice() {
+        let attacker = WarcryModel::new();
+        let mut defender = WarcryModel::new();
+        defender.toughness = 3;
+        let mut options = WarcryOptions::new();
+        options.num_attack_actions = 2;
+        let dmg_probs = calc_dmg_probs(&attacker, &defender, &options);
+        // 6 dice hitting on 3+, 3 dmg per crit
+        assert!((dmg_probs[&18] - (1.0f64 / 6.0).powi(6)).abs() < 1e-15);
+        assert!((dmg_probs[&0] - (1.0f64 / 3.0).powi(6)).abs() < 1e-15);
+        assert_eq!(calc_hit_pip(3, 4), 5);
+    }
+}
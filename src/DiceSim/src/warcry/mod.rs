@@ -0,0 +1,8 @@
+mod calculator;
+#[cfg(feature = "test-vectors")]
+mod test_vectors;
+mod warcry_model;
+mod warcry_options;
+
+#[cfg(feature = "test-vectors")]
+pub(crate) use test_vectors::test_vectors;
@@ -0,0 +1,15 @@
+use super::calculator::calc_dmg_probs;
+use super::warcry_model::WarcryModel;
+use super::warcry_options::WarcryOptions;
+use crate::test_vectors::flag_combo_test_vectors;
+
+// no rule flags, so the one flag is whether the activation's second action is an attack too
+flag_combo_test_vectors!(
+    "warcry",
+    calc_dmg_probs {
+        attacker: WarcryModel::new(),
+        defender: WarcryModel::new(),
+        options: WarcryOptions::new(),
+    },
+    [options.num_attack_actions = [1, 2]],
+);
@@ -0,0 +1,58 @@
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+use crate::common::stat_validation::{RangedStats, MAX_COUNT_STAT};
+use crate::common::ts_types::StatRanges;
+
+#[wasm_bindgen]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WarcryModel {
+    pub attacks: i32,
+    pub strength: i32,
+    #[wasm_bindgen(js_name = hitDmg)]
+    pub hit_dmg: i32,
+    #[wasm_bindgen(js_name = critDmg)]
+    pub crit_dmg: i32,
+    pub toughness: i32,
+}
+
+#[wasm_bindgen]
+impl WarcryModel {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> WarcryModel {
+        WarcryModel {
+            attacks: 3,
+            strength: 4,
+            hit_dmg: 1,
+            crit_dmg: 3,
+            toughness: 4,
+        }
+    }
+}
+
+// only strength vs toughness matters to hitting, so neither needs an upper bound
+impl RangedStats for WarcryModel {
+    const STAT_RANGES: &'static [(&'static str, i32, i32)] = &[
+        ("attacks", 0, MAX_COUNT_STAT),
+        ("strength", 1, i32::MAX),
+        ("hitDmg", 0, MAX_COUNT_STAT),
+        ("critDmg", 0, MAX_COUNT_STAT),
+        ("toughness", 1, i32::MAX),
+    ];
+
+    fn stat_values(&self) -> Vec<i32> {
+        vec![
+            self.attacks,
+            self.strength,
+            self.hit_dmg,
+            self.crit_dmg,
+            self.toughness,
+        ]
+    }
+}
+
+#[wasm_bindgen(js_name = "warcryStatRanges")]
+pub fn warcry_stat_ranges() -> StatRanges {
+    StatRanges::from_ranges(WarcryModel::STAT_RANGES)
+}
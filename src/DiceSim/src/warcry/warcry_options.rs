@@ -0,0 +1,30 @@
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+use crate::common::stat_validation::RangedStats;
+
+#[wasm_bindgen]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WarcryOptions {
+    #[wasm_bindgen(js_name = numAttackActions)]
+    pub num_attack_actions: i32, // 1 or 2, since a fighter gets two actions per activation
+}
+
+#[wasm_bindgen]
+impl WarcryOptions {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> WarcryOptions {
+        WarcryOptions {
+            num_attack_actions: 1,
+        }
+    }
+}
+
+impl RangedStats for WarcryOptions {
+    const STAT_RANGES: &'static [(&'static str, i32, i32)] = &[("numAttackActions", 0, 2)];
+
+    fn stat_values(&self) -> Vec<i32> {
+        vec![self.num_attack_actions]
+    }
+}
@@ -0,0 +1,182 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
+use wasm_bindgen::prelude::*;
+
+use super::xwing_model::XWingModel;
+use crate::common::add_to_map_value;
+use crate::common::calc_error::CalcError;
+use crate::common::stat_validation::RangedStats;
+use crate::common::ts_types::{result_schema_v1, ProbMap, RESULT_SCHEMA_VERSION};
+
+// faces out of 8 sides
+const ATK_CRIT_PROB: f64 = 1.0 / 8.0;
+const ATK_HIT_PROB: f64 = 3.0 / 8.0;
+const ATK_FOCUS_PROB: f64 = 2.0 / 8.0;
+const ATK_BLANK_PROB: f64 = 2.0 / 8.0;
+const DEF_EVADE_PROB: f64 = 3.0 / 8.0;
+const DEF_FOCUS_PROB: f64 = 2.0 / 8.0;
+
+#[derive(Tsify, Serialize, Deserialize)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+#[serde(rename_all = "camelCase")]
+pub struct XWingAttackResult {
+    #[serde(default = "result_schema_v1")]
+    pub result_schema_version: u32,
+    pub hit_probs: ProbMap,  // uncanceled hits
+    pub crit_probs: ProbMap, // uncanceled crits
+    pub dmg_probs: ProbMap,  // uncanceled hits plus crits
+}
+
+#[wasm_bindgen(js_name = "xWingCalcAttackProbs")]
+pub fn xwing_calc_attack_probs(
+    attacker: &XWingModel,
+    defender: &XWingModel,
+) -> Result<XWingAttackResult, CalcError> {
+    attacker.validate_stats()?;
+    defender.validate_stats()?;
+    Ok(calc_attack_probs(attacker, defender))
+}
+
+pub fn calc_attack_probs(attacker: &XWingModel, defender: &XWingModel) -> XWingAttackResult {
+    let hit_crit_probs = calc_hit_crit_probs(attacker, defender);
+    let mut hit_probs = HashMap::<i32, f64>::new();
+    let mut crit_probs = HashMap::<i32, f64>::new();
+    let mut dmg_probs = HashMap::<i32, f64>::new();
+
+    for ((hits, crits), prob) in hit_crit_probs.iter() {
+        add_to_map_value(&mut hit_probs, hits, *prob);
+        add_to_map_value(&mut crit_probs, crits, *prob);
+        add_to_map_value(&mut dmg_probs, &(hits + crits), *prob);
+    }
+
+    XWingAttackResult {
+        result_schema_version: RESULT_SCHEMA_VERSION,
+        hit_probs: ProbMap(hit_probs),
+        crit_probs: ProbMap(crit_probs),
+        dmg_probs: ProbMap(dmg_probs),
+    }
+}
+
+// joint distribution of (uncanceled hits, uncanceled crits); evades cancel hits before crits
+pub fn calc_hit_crit_probs(
+    attacker: &XWingModel,
+    defender: &XWingModel,
+) -> HashMap<(i32, i32), f64> {
+    let (crit_prob, hit_prob) = modified_attack_die_probs(attacker);
+    let evade_prob = if defender.has_focus {
+        DEF_EVADE_PROB + DEF_FOCUS_PROB
+    } else {
+        DEF_EVADE_PROB
+    };
+
+    let mut atk_probs = HashMap::from([((0, 0), 1.0)]);
+    for _ in 0..attacker.num_dice {
+        let mut next_atk_probs = HashMap::<(i32, i32), f64>::new();
+        for ((hits, crits), prob) in atk_probs.iter() {
+            add_to_map_value(&mut next_atk_probs, &(*hits, crits + 1), prob * crit_prob);
+            add_to_map_value(&mut next_atk_probs, &(hits + 1, *crits), prob * hit_prob);
+            add_to_map_value(
+                &mut next_atk_probs,
+                &(*hits, *crits),
+                prob * (1.0 - crit_prob - hit_prob),
+            );
+        }
+        atk_probs = next_atk_probs;
+    }
+
+    let mut evade_probs = HashMap::from([(0, 1.0)]);
+    for _ in 0..defender.num_dice {
+        let mut next_evade_probs = HashMap::<i32, f64>::new();
+        for (evades, prob) in evade_probs.iter() {
+            add_to_map_value(&mut next_evade_probs, &(evades + 1), prob * evade_prob);
+            add_to_map_value(&mut next_evade_probs, evades, prob * (1.0 - evade_prob));
+        }
+        evade_probs = next_evade_probs;
+    }
+
+    let mut hit_crit_probs = HashMap::<(i32, i32), f64>::new();
+    for ((hits, crits), atk_prob) in atk_probs.iter() {
+        for (evades, def_prob) in evade_probs.iter() {
+            let uncanceled_hits = std::cmp::max(0, hits - evades);
+            let evades_left_for_crits = std::cmp::max(0, evades - hits);
+            let uncanceled_crits = std::cmp::max(0, crits - evades_left_for_crits);
+            add_to_map_value(
+                &mut hit_crit_probs,
+                &(uncanceled_hits, uncanceled_crits),
+                atk_prob * def_prob,
+            );
+        }
+    }
+    hit_crit_probs
+}
+
+// (crit prob, hit prob) for a single attack die after target lock rerolls and focus spending
+fn modified_attack_die_probs(attacker: &XWingModel) -> (f64, f64) {
+    let rerolled_prob = if !attacker.has_target_lock {
+        0.0
+    } else if attacker.has_focus {
+        ATK_BLANK_PROB
+    } else {
+        ATK_BLANK_PROB + ATK_FOCUS_PROB
+    };
+    // rerolled dice can't be rerolled again, but their focus results can still be spent
+    let roll_count = 1.0 + rerolled_prob;
+    let crit_prob = ATK_CRIT_PROB * roll_count;
+    let mut hit_prob = ATK_HIT_PROB * roll_count;
+    if attacker.has_focus {
+        hit_prob += ATK_FOCUS_PROB * roll_count;
+    }
+    (crit_prob, hit_prob)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn xwing_model(num_dice: i32, has_focus: bool, has_target_lock: bool) -> XWingModel {
+        XWingModel {
+            num_dice,
+            has_focus,
+            has_target_lock,
+        }
+    }
+
+    #[test]
+    fn target_lock_rerolls_blanks_and_unspent_focus() {
+        assert_eq!(
+            modified_attack_die_probs(&xwing_model(1, false, false)),
+            (1.0 / 8.0, 3.0 / 8.0)
+        );
+        assert_eq!(
+            modified_attack_die_probs(&xwing_model(1, true, false)),
+            (1.0 / 8.0, 5.0 / 8.0)
+        );
+        assert_eq!(
+            modified_attack_die_probs(&xwing_model(1, false, true)),
+            (3.0 / 16.0, 9.0 / 16.0)
+        );
+        assert_eq!(
+            modified_attack_die_probs(&xwing_model(1, true, true)),
+            (5.0 / 32.0, 25.0 / 32.0)
+        );
+    }
+
+    // one evade cancels the hit first, so a crit only gets through when there is no hit to cancel
+    #[test]
+    fn evades_cancel_hits_before_crits() {
+        let hit_crit_probs =
+            calc_hit_crit_probs(&xwing_model(2, false, false), &xwing_model(1, false, false));
+        let no_evade_prob = 5.0 / 8.0;
+        let one_hit_one_crit_prob = 2.0 * (3.0 / 8.0) * (1.0 / 8.0);
+        assert!((hit_crit_probs[&(1, 1)] - one_hit_one_crit_prob * no_evade_prob).abs() < 1e-12);
+        // a hit and a crit with the hit evaded, a crit and a blank unevaded, or two crits with one evaded
+        let one_crit_prob = one_hit_one_crit_prob * (1.0 - no_evade_prob)
+            + 2.0 * (1.0 / 8.0) * (4.0 / 8.0) * no_evade_prob
+            + (1.0 / 8.0) * (1.0 / 8.0) * (1.0 - no_evade_prob);
+        assert!((hit_crit_probs[&(0, 1)] - one_crit_prob).abs() < 1e-12);
+        let total: f64 = hit_crit_probs.values().sum();
+        assert!((total - 1.0).abs() < 1e-12);
+    }
+}
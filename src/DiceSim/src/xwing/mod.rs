@@ -0,0 +1,7 @@
+mod calculator;
+#[cfg(feature = "test-vectors")]
+mod test_vectors;
+mod xwing_model;
+
+#[cfg(feature = "test-vectors")]
+pub(crate) use test_vectors::test_vectors;
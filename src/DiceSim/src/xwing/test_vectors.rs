@@ -0,0 +1,20 @@
+use super::calculator::calc_attack_probs;
+use super::xwing_model::XWingModel;
+use crate::test_vectors::flag_combo_test_vectors;
+
+// defenders can't use target locks, so that's only varied for the attacker
+flag_combo_test_vectors!(
+    "xWing",
+    calc_attack_probs {
+        attacker: XWingModel {
+            num_dice: 3,
+            ..XWingModel::new()
+        },
+        defender: XWingModel::new(),
+    },
+    [
+        attacker.has_focus = [false, true],
+        attacker.has_target_lock = [false, true],
+        defender.has_focus = [false, true],
+    ],
+);
@@ -0,0 +1,42 @@
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+use crate::common::stat_validation::{RangedStats, MAX_COUNT_STAT};
+use crate::common::ts_types::StatRanges;
+
+#[wasm_bindgen]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct XWingModel {
+    #[wasm_bindgen(js_name = numDice)]
+    pub num_dice: i32,
+    #[wasm_bindgen(js_name = hasFocus)]
+    pub has_focus: bool, // converts all focus results to hits (attacker) or evades (defender)
+    #[wasm_bindgen(js_name = hasTargetLock)]
+    pub has_target_lock: bool, // attacker only; rerolls blanks, and also focus results if no focus token
+}
+
+#[wasm_bindgen]
+impl XWingModel {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> XWingModel {
+        XWingModel {
+            num_dice: 2,
+            has_focus: false,
+            has_target_lock: false,
+        }
+    }
+}
+
+impl RangedStats for XWingModel {
+    const STAT_RANGES: &'static [(&'static str, i32, i32)] = &[("numDice", 0, MAX_COUNT_STAT)];
+
+    fn stat_values(&self) -> Vec<i32> {
+        vec![self.num_dice]
+    }
+}
+
+#[wasm_bindgen(js_name = "xWingStatRanges")]
+pub fn xwing_stat_ranges() -> StatRanges {
+    StatRanges::from_ranges(XWingModel::STAT_RANGES)
+}
@@ -5,9 +5,49 @@ import Col from 'react-bootstrap/Col';
 
 import IncDecSelect, {Props as IncProps} from 'src/components/IncDecSelect';
 import * as Util from 'src/Util';
-import { DeadzoneModel, } from "src/DiceSim/pkg/dice_sim";
+import {
+  DeadzoneModel,
+  StatRange,
+  WeaponPreset,
+  deadzoneApplyWeaponPreset,
+  deadzoneQueryWeaponPresets,
+  deadzoneStatRanges,
+} from "src/DiceSim/pkg/dice_sim";
 
 
+// the engine's limits on each stat, so the inputs can't offer a value it rejects;
+// fetched on first use, since wasm isn't initialized yet when this module loads
+let statRanges: Map<string, StatRange> | undefined;
+
+// the values shown are whatever part of [first, last] the engine accepts
+function statSpan(name: string, first: number, last: number, suffix?: string) : string[] {
+  if (statRanges === undefined) {
+    statRanges = new Map(deadzoneStatRanges().items.map(range => [range.name, range]));
+  }
+  const range = statRanges.get(name);
+  return range === undefined
+    ? Util.span(first, last, suffix)
+    : Util.span(Math.max(first, range.min), Math.min(last, range.max), suffix);
+}
+
+const customWeapon = 'Custom';
+let weaponPresets: WeaponPreset[] | undefined;
+
+// fetched on first use, like the stat ranges
+function getWeaponPresets() : WeaponPreset[] {
+  if (weaponPresets === undefined) {
+    weaponPresets = deadzoneQueryWeaponPresets('', '').items;
+  }
+  return weaponPresets;
+}
+
+// the preset whose stats the model has, if any, so picking one and then editing a stat shows Custom
+function matchingWeaponName(model: DeadzoneModel) : string {
+  const preset = getWeaponPresets().find(preset =>
+    preset.numDice === model.numDice && preset.ap === model.ap && preset.toxicDmg === model.toxicDmg);
+  return preset === undefined ? customWeapon : preset.name;
+}
+
 export interface Props {
   model: DeadzoneModel;
   isAttacker: boolean;
@@ -18,19 +58,68 @@ const ModelControls: React.FC<Props> = (props: Props) => {
   const model = props.model;
   const [textHandler, numHandler, boolHandler]
     = Util.makePropChangeHandlers(model, props.changeHandler);
-  const diceSpan = Util.span(props.isAttacker ? 1 : 0, 9);
-  const intSpan = Util.span(0, 9);
+  const diceSpan = statSpan('numDice', props.isAttacker ? 1 : 0, 9);
+  const intSpan = (name: string) => statSpan(name, 0, 9);
+  const maxDmgToDisplayTexts = new Map<number,string>([
+    [0x7fffffff, 'Unlimited'],
+    [1, '1'],
+    [2, '2'],
+    [3, '3'],
+    [4, '4'],
+  ]);
+  const instantKillMarginToDisplayTexts = new Map<number,string>([
+    [0x7fffffff, 'Never'],
+    [2, '2'],
+    [3, '3'],
+    [4, '4'],
+    [5, '5'],
+  ]);
+  const numDiceKeptToDisplayTexts = new Map<number,string>([
+    [0x7fffffff, 'All'],
+    [1, '1'],
+    [2, '2'],
+    [3, '3'],
+    [4, '4'],
+  ]);
+  const maxSuccessesToDisplayTexts = new Map<number,string>([
+    [0x7fffffff, 'Unlimited'],
+    [1, '1'],
+    [2, '2'],
+    [3, '3'],
+    [4, '4'],
+  ]);
+  const maxSuccessesIncProps = Util.makeIncDecPropsFromLookup('MaxSuccesses', model, props.changeHandler, 'maxSuccesses', maxSuccessesToDisplayTexts);
+  const numDiceKeptIncProps = Util.makeIncDecPropsFromLookup('DiceKept', model, props.changeHandler, 'numDiceKept', numDiceKeptToDisplayTexts);
+  const instantKillMarginIncProps = Util.makeIncDecPropsFromLookup('InstaKillMargin', model, props.changeHandler, 'instantKillMargin', instantKillMarginToDisplayTexts);
+  const maxDmgIncProps = Util.makeIncDecPropsFromLookup('MaxDmg', model, props.changeHandler, 'maxDmg', maxDmgToDisplayTexts);
+
+  const weaponNames = [customWeapon].concat(getWeaponPresets().map(preset => preset.name));
+  const weaponHandler = (pickedName: string) => {
+    if (pickedName !== customWeapon) {
+      props.changeHandler(deadzoneApplyWeaponPreset(model, pickedName));
+    }
+  };
 
   let params: IncProps[] = [
     //           id/label,            selectedValue,        values,               valueChangeHandler
-    new IncProps('HP',                model.hp,             Util.span(1,10),      numHandler('hp')),
-    new IncProps('Dice',              model.numDice,        diceSpan,             numHandler('numDice')),
-    new IncProps('Stat(RA/FI/SV)',    model.diceStat + "+", Util.span(1, 8, '+'), numHandler('diceStat')),
-    new IncProps('Rerolls',           model.numRerolls,     intSpan,              numHandler('numRerolls')),
-    new IncProps('Toxic/Dismantle',   model.toxicDmg,       intSpan,              numHandler('toxicDmg')),
-    new IncProps('AP',                model.ap,             intSpan,              numHandler('ap')),
-    new IncProps('Armor',             model.armor,          intSpan,              numHandler('armor')),
-    new IncProps('ShieldDice',        model.numShieldDice,  intSpan,              numHandler('numShieldDice')),
+    new IncProps('HP',             model.hp,             statSpan('hp', 1, 10), numHandler('hp')),
+    new IncProps('Weapon',         matchingWeaponName(model), weaponNames, weaponHandler),
+    new IncProps('Dice',           model.numDice,        diceSpan,             numHandler('numDice')),
+    new IncProps('Stat(RA/FI/SV)', model.diceStat + "+", statSpan('diceStat', 1, 8, '+'), numHandler('diceStat')),
+    new IncProps('Rerolls',        model.numRerolls,     intSpan('numRerolls'), numHandler('numRerolls')),
+    numDiceKeptIncProps,
+    maxSuccessesIncProps,
+    new IncProps('Toxic/Dismantle', model.toxicDmg,       intSpan('toxicDmg'),  numHandler('toxicDmg')),
+    new IncProps('AP',             model.ap,             intSpan('ap'),        numHandler('ap')),
+    new IncProps('AP/ExcessSuccess', model.apPerExcessSuccess, statSpan('apPerExcessSuccess', 0, 3), numHandler('apPerExcessSuccess')),
+    new IncProps('Armor',          model.armor,          intSpan('armor'),     numHandler('armor')),
+    new IncProps('ShieldDice',     model.numShieldDice,  intSpan('numShieldDice'), numHandler('numShieldDice')),
+    new IncProps('ShieldRerolls',  model.numShieldRerolls, intSpan('numShieldRerolls'), numHandler('numShieldRerolls')),
+    new IncProps('ShieldPipMod',   model.shieldPipModifier, statSpan('shieldPipModifier', -2, 2), numHandler('shieldPipModifier')),
+    new IncProps('SuccessesPerDmg', model.successesPerDmg, statSpan('successesPerDmg', 1, 4), numHandler('successesPerDmg')),
+    maxDmgIncProps,
+    instantKillMarginIncProps,
+    new IncProps('Pinned?',        Util.boolToCheckX(model.pinned), Util.xAndCheck,       boolHandler('pinned')),
   ];
 
   const paramElems = params.map(p =>
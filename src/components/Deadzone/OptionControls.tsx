@@ -4,7 +4,7 @@ import Row from 'react-bootstrap/Row';
 import Col from 'react-bootstrap/Col';
 
 import IncDecSelect, {Props as IncProps, propsToRows} from 'src/components/IncDecSelect';
-import { DeadzoneOptions } from "src/DiceSim/pkg/dice_sim";
+import { DeadzoneEdition, DeadzoneOptions } from "src/DiceSim/pkg/dice_sim";
 import {
   Accepter,
   makePropChangeHandlers,
@@ -27,6 +27,7 @@ const OptionControls: React.FC<Props> = (props: Props) => {
     = makePropChangeHandlers(opts, props.changeHandler);
 
   const simCountToDisplayTexts = new Map<number,string>([
+    [0, 'Exact'],
     [1, '1'],
     [1e2, '100'],
     [1e3, '1K'],
@@ -44,14 +45,50 @@ const OptionControls: React.FC<Props> = (props: Props) => {
   ]);
   const explodingDiceMaxLevelsIncProps = makeIncDecPropsFromLookup('ExplodingDiceLevels', opts, props.changeHandler, 'explodingDiceMaxLevels', explodingDiceMaxLevelsToDisplayTexts);
 
+  const outputDecimalsToDisplayTexts = new Map<number,string>([
+    [0x7fffffff, 'Unrounded'],
+    [2, '2'],
+    [3, '3'],
+    [4, '4'],
+  ]);
+  const outputDecimalsIncProps = makeIncDecPropsFromLookup('OutputDecimals', opts, props.changeHandler, 'outputDecimals', outputDecimalsToDisplayTexts);
+  const outputMinProbToDisplayTexts = new Map<number,string>([
+    [0, 'Keep all'],
+    [1e-4, '0.01%'],
+    [1e-3, '0.1%'],
+  ]);
+  const outputMinProbIncProps = makeIncDecPropsFromLookup('OutputMinProb', opts, props.changeHandler, 'outputMinProb', outputMinProbToDisplayTexts);
+
+  const editionToDisplayTexts = new Map<number,string>([
+    [DeadzoneEdition.Third, '3rd'],
+    [DeadzoneEdition.Second, '2nd'],
+  ]);
+  const editionIncProps = makeIncDecPropsFromLookup('Edition', opts, props.changeHandler, 'edition', editionToDisplayTexts);
+
   const fightBackVal = boolToCheckX(opts.attackerCanBeDamaged);
+  const aimedVal = boolToCheckX(opts.aimed);
+  const stopWhenDeadVal = boolToCheckX(opts.stopWhenDead);
 
   const params: IncProps[] = [
     //           id,              selectedValue,          values,      valueChangeHandler
     new IncProps('FightBack?',    fightBackVal,           xAndCheck,   boolHandler('attackerCanBeDamaged')),
+    new IncProps('Aimed?',        aimedVal,               xAndCheck,   boolHandler('aimed')),
+    new IncProps('AtkAssists',    opts.attackerNumAssists, span(0, 4), numHandler('attackerNumAssists')),
+    new IncProps('DefAssists',    opts.defenderNumAssists, span(0, 4), numHandler('defenderNumAssists')),
+    new IncProps('ChargeDice',    opts.chargeBonusDice,   span(0, 3),  numHandler('chargeBonusDice')),
+    new IncProps('ChargePipBonus', opts.chargePipBonus,   span(0, 2),  numHandler('chargePipBonus')),
+    new IncProps('StanceDice',    opts.defensiveStanceDice, span(0, 3), numHandler('defensiveStanceDice')),
+    new IncProps('AtkForcedRerolls', opts.attackerForcedRerolls, span(0, 3), numHandler('attackerForcedRerolls')),
+    new IncProps('DefForcedRerolls', opts.defenderForcedRerolls, span(0, 3), numHandler('defenderForcedRerolls')),
+    new IncProps('CoverDice',     opts.numCoverDice,      span(0, 4),  numHandler('numCoverDice')),
+    new IncProps('CoverStat',     opts.coverDiceStat + "+", span(2, 8, '+'), numHandler('coverDiceStat')),
     simCountIncProps,
     new IncProps('Rounds',        opts.numRounds,         span(1, 9),  numHandler('numRounds')),
+    new IncProps('StopWhenDead?', stopWhenDeadVal,        xAndCheck,   boolHandler('stopWhenDead')),
     explodingDiceMaxLevelsIncProps,
+    editionIncProps,
+    outputMinProbIncProps,
+    outputDecimalsIncProps,
   ];
 
   const [elemsCol0, elemsCol1] = requiredAndOptionalItemsToTwoCols(propsToRows(params));